@@ -0,0 +1,129 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// A set of non-overlapping ranges of an address space, each with an
+/// occupant describing what was placed there.
+///
+/// Ranges are half-open, `start..start + size`. An empty range never
+/// overlaps anything and is never stored.
+///
+/// Blocks are kept ordered by their start. Because they never overlap, the
+/// only block which can overlap a range is the last one starting before
+/// the range ends, which makes lookups and insertions `O(log n)`.
+#[derive(Debug, Clone)]
+pub struct AddressSpace<T> {
+    // start -> (end, occupant)
+    blocks: BTreeMap<usize, (usize, T)>,
+}
+
+impl<T> Default for AddressSpace<T> {
+    fn default() -> Self {
+        Self {
+            blocks: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> AddressSpace<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of ranges placed.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Returns `true` if any part of `start..start + size` is occupied.
+    pub fn is_used(&self, start: usize, size: usize) -> bool {
+        self.occupants(start, size).next().is_some()
+    }
+
+    /// Places `occupant` at `start..start + size`. Returns `false`, leaving
+    /// the address space unchanged, if the range is empty or overlaps a
+    /// range which is already occupied.
+    pub fn insert(&mut self, start: usize, size: usize, occupant: T) -> bool {
+        if size == 0 || self.is_used(start, size) {
+            return false;
+        }
+        self.blocks.insert(start, (start + size, occupant));
+        true
+    }
+
+    /// Returns every occupied range overlapping `start..start + size`, with
+    /// its occupant, in ascending order.
+    pub fn occupants(
+        &self,
+        start: usize,
+        size: usize,
+    ) -> impl DoubleEndedIterator<Item = (Range<usize>, &T)> {
+        let end = start + size;
+        // blocks starting before the range ends, which still end after it
+        // starts. since blocks don't overlap, these are contiguous.
+        let overlapping = if size == 0 {
+            self.blocks.range(0..0)
+        } else {
+            let first = match self.blocks.range(..=start).next_back() {
+                Some((&block_start, &(block_end, _))) if block_end > start => block_start,
+                _ => start,
+            };
+            self.blocks.range(first..end)
+        };
+
+        overlapping.map(|(&block_start, (block_end, occupant))| (block_start..*block_end, occupant))
+    }
+
+    /// Returns every occupied range with its occupant, in ascending order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (Range<usize>, &T)> {
+        self.blocks
+            .iter()
+            .map(|(&start, (end, occupant))| (start..*end, occupant))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_space() {
+        let mut space = AddressSpace::new();
+        assert!(space.insert(0x100, 0x10, "a"));
+        assert!(space.insert(0x120, 0x10, "b"));
+        assert_eq!(space.len(), 2);
+
+        // overlapping the start, the end, inside, and containing a block
+        assert!(space.is_used(0xF8, 0x10));
+        assert!(space.is_used(0x108, 0x10));
+        assert!(space.is_used(0x104, 0x4));
+        assert!(space.is_used(0x80, 0x100));
+        assert!(!space.insert(0x80, 0x100, "c"));
+
+        // touching is not overlapping
+        assert!(!space.is_used(0xF0, 0x10));
+        assert!(!space.is_used(0x110, 0x10));
+        assert!(!space.is_used(0x130, 0x10));
+        assert!(!space.is_used(0x104, 0));
+        assert!(!space.insert(0x110, 0, "empty"));
+
+        assert!(space.insert(0x110, 0x10, "d"));
+        let occupants = space
+            .occupants(0x108, 0x20)
+            .map(|(range, occupant)| (range, *occupant))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            occupants,
+            vec![
+                (0x100..0x110, "a"),
+                (0x110..0x120, "d"),
+                (0x120..0x130, "b")
+            ]
+        );
+    }
+}
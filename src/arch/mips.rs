@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: © 2025 TTKB, LLC
 // SPDX-License-Identifier: BSD-3-CLAUSE
 
-use std::fmt::{self, Debug, Formatter};
+use std::fmt::Debug;
 
 use rabbitizer::InstrCategory;
 use rabbitizer::Instruction;
@@ -9,7 +9,7 @@ use rabbitizer::OperandType;
 
 use crate::MIPSFamily;
 
-trait MIPSCategory {
+pub trait MIPSCategory {
     fn category(&self) -> InstrCategory;
 }
 
@@ -144,8 +144,7 @@ pub fn read_word(bytes: &[u8], family: MIPSFamily) -> u32 {
     }
 }
 
-pub fn normalize_instruction(instruction: u32, family: MIPSFamily) -> u32 {
-    let _i = Instruction::new(instruction, 0, family.category());
+pub fn normalize_instruction(instruction: u32, _family: MIPSFamily) -> u32 {
     // // mask any fields which may refer to global symbols. this will
     // // mask false positives, but keep most immediates and local vars.
 
@@ -181,6 +180,159 @@ pub fn normalize_instruction(instruction: u32, family: MIPSFamily) -> u32 {
     }
 }
 
+// MIPS relocation types, see the System V ABI MIPS supplement
+pub const R_MIPS_NONE: u32 = 0;
+pub const R_MIPS_16: u32 = 1;
+pub const R_MIPS_32: u32 = 2;
+pub const R_MIPS_REL32: u32 = 3;
+pub const R_MIPS_26: u32 = 4;
+pub const R_MIPS_HI16: u32 = 5;
+pub const R_MIPS_LO16: u32 = 6;
+pub const R_MIPS_GPREL16: u32 = 7;
+pub const R_MIPS_LITERAL: u32 = 8;
+pub const R_MIPS_GOT16: u32 = 9;
+pub const R_MIPS_PC16: u32 = 10;
+pub const R_MIPS_CALL16: u32 = 11;
+pub const R_MIPS_GPREL32: u32 = 12;
+
+/// The bits of an instruction filled in by a relocation of type `r_type`.
+/// Unknown relocations are assumed to cover the whole word.
+pub fn relocation_mask(r_type: u32) -> u32 {
+    match r_type {
+        R_MIPS_NONE => 0,
+        R_MIPS_26 => 0x03FFFFFF,
+        R_MIPS_16 | R_MIPS_HI16 | R_MIPS_LO16 | R_MIPS_GPREL16 | R_MIPS_LITERAL | R_MIPS_GOT16
+        | R_MIPS_PC16 | R_MIPS_CALL16 => 0x0000FFFF,
+        R_MIPS_32 | R_MIPS_REL32 | R_MIPS_GPREL32 => 0xFFFFFFFF,
+        _ => 0xFFFFFFFF,
+    }
+}
+
+// opcodes
+pub const OP_SPECIAL: u32 = 0x00;
+pub const OP_J: u32 = 0x02;
+pub const OP_JAL: u32 = 0x03;
+pub const OP_ADDIU: u32 = 0x09;
+pub const OP_SLTIU: u32 = 0x0B;
+pub const OP_ORI: u32 = 0x0D;
+pub const OP_LUI: u32 = 0x0F;
+pub const OP_LW: u32 = 0x23;
+
+// functions of the SPECIAL opcode
+pub const FUNCT_JR: u32 = 0x08;
+pub const FUNCT_ADDU: u32 = 0x21;
+
+pub const REG_RA: usize = 31;
+
+/// The bits of an address a `j` or `jal` encodes. The rest are those of the
+/// address of its delay slot.
+pub const TARGET_MASK: usize = 0x0FFFFFFF;
+
+/// The low 28 bits of the target of a `j` or `jal`.
+pub fn jump_target(instruction: u32) -> usize {
+    ((instruction & 0x03FFFFFF) << 2) as usize
+}
+
+/// The general purpose register `instruction` writes to, if any.
+pub fn destination_register(instruction: u32) -> Option<usize> {
+    let rt = ((instruction >> 16) & 0x1F) as usize;
+    let rd = ((instruction >> 11) & 0x1F) as usize;
+    match instruction >> 26 {
+        OP_SPECIAL => Some(rd),
+        OP_JAL => Some(REG_RA),
+        // immediate arithmetic and loads
+        0x08..=0x0F | 0x20..=0x27 => Some(rt),
+        _ => None,
+    }
+    .filter(|register| *register != 0)
+}
+
+/// What is known about the value of a register, see [`Registers`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value {
+    Unknown,
+    /// An address built with `lui`, and possibly `addiu` or `ori`.
+    Address(u32),
+    /// An address with an index added to it.
+    Indexed(u32),
+    /// A word loaded from an indexed address.
+    Loaded(u32),
+}
+
+/// Follows the addresses built in registers by `lui` and `addiu` or
+/// `ori`, as well as the indexed loads jump tables are dispatched with.
+/// Code is followed in order without regard for branches, which is enough
+/// for the sequences compilers emit.
+pub struct Registers([Value; 32]);
+
+impl Registers {
+    pub fn new() -> Self {
+        Self([Value::Unknown; 32])
+    }
+
+    /// The value of `register` before the next instruction.
+    pub fn get(&self, register: usize) -> Value {
+        self.0[register]
+    }
+
+    /// Follows `instruction`. Returns the address it completes, when it
+    /// adds an `addiu` or `ori` immediate to an address.
+    pub fn step(&mut self, instruction: u32) -> Option<u32> {
+        let rs = ((instruction >> 21) & 0x1F) as usize;
+        let rt = ((instruction >> 16) & 0x1F) as usize;
+        let imm = instruction & 0xFFFF;
+        let simm = imm as i16 as i32 as u32;
+
+        let mut completed = None;
+        let value = match (instruction >> 26, instruction & 0x3F) {
+            (OP_LUI, _) => Value::Address(imm << 16),
+            (OP_ADDIU, _) | (OP_ORI, _) => match self.0[rs] {
+                Value::Address(upper) => {
+                    let address = if instruction >> 26 == OP_ORI {
+                        upper | imm
+                    } else {
+                        upper.wrapping_add(simm)
+                    };
+                    completed = Some(address);
+                    Value::Address(address)
+                }
+                _ => Value::Unknown,
+            },
+            (OP_SPECIAL, FUNCT_ADDU) => match (self.0[rs], self.0[rt]) {
+                (Value::Address(address), _) | (_, Value::Address(address)) => {
+                    Value::Indexed(address)
+                }
+                _ => Value::Unknown,
+            },
+            (OP_LW, _) => match self.0[rs] {
+                Value::Indexed(address) => Value::Loaded(address.wrapping_add(simm)),
+                _ => Value::Unknown,
+            },
+            _ => Value::Unknown,
+        };
+
+        if let Some(register) = destination_register(instruction) {
+            self.0[register] = value;
+        }
+        completed
+    }
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The little endian bytes of `instructions`, for tests.
+#[cfg(test)]
+pub fn assemble(instructions: &[u32]) -> Vec<u8> {
+    instructions
+        .iter()
+        .flat_map(|instruction| instruction.to_le_bytes())
+        .collect()
+}
+
 #[derive(Eq, Hash, Debug, PartialEq)]
 pub enum BinFormat {
     BigEndian,
@@ -218,7 +370,7 @@ pub fn determine_bin_fmt(bytes: &[u8]) -> Option<BinFormat> {
     let mut bs_count: usize = 0;
     let mut ls_count: usize = 0;
 
-    for i in bytes.chunks(4).map(|b| be_bytes_to_u32(b)) {
+    for i in bytes.chunks(4).map(be_bytes_to_u32) {
         match i {
             BE_JR_RA => be_count += 1,
             LE_JR_RA => le_count += 1,
@@ -245,6 +397,41 @@ pub fn determine_bin_fmt(bytes: &[u8]) -> Option<BinFormat> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_registers() {
+        let mut registers = Registers::new();
+        let steps = [
+            0x3C048010, // lui   $a0, 0x8010
+            0x24840010, // addiu $a0, $a0, 0x10
+            0x3485FF00, // ori   $a1, $a0, 0xFF00
+            0x00A61021, // addu  $v0, $a1, $a2
+            0x8C42FFFC, // lw    $v0, -4($v0)
+            0x24860004, // addiu $a2, $a0, 4
+            0x24840004, // addiu $a0, $a0, 4
+            0x00002021, // addu  $a0, $zero, $zero
+            0x24840004, // addiu $a0, $a0, 4
+        ]
+        .map(|instruction| registers.step(instruction));
+        assert_eq!(
+            steps,
+            [
+                None,
+                Some(0x80100010),
+                Some(0x8010FF10),
+                None,
+                None,
+                Some(0x80100014),
+                Some(0x80100014),
+                None,
+                None,
+            ]
+        );
+        assert_eq!(registers.get(2), Value::Loaded(0x8010FF0C));
+        assert_eq!(registers.get(5), Value::Address(0x8010FF10));
+        assert_eq!(registers.get(6), Value::Address(0x80100014));
+        assert_eq!(registers.get(4), Value::Unknown);
+    }
+
     #[test]
     fn bytes_to_instruction() {
         let result = bytes_to_le_instruction(&[3, 2, 1, 0]);
@@ -347,14 +534,14 @@ mod bench {
     fn shift_to_32() {
         let mut t: u128 = 0;
         let (_, impl_time) = time(|| {
-            for i in 0..10_000_000 {
+            for _ in 0..10_000_000 {
                 t += le_bytes_to_u32(&LE_JR_RA_BYTES) as u128;
             }
         });
 
         t = 0;
         let (_, std_time) = time(|| {
-            for i in 0..10_000_000 {
+            for _ in 0..10_000_000 {
                 t += u32::from_le_bytes(LE_JR_RA_BYTES[0..4].try_into().unwrap()) as u128;
             }
         });
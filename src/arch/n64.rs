@@ -1,7 +1,7 @@
 // Convert to Z64
 
 pub fn n64_to_z64(bytes: &[u8]) -> Vec<u8> {
-    assert!(bytes.len() % 2 == 0);
+    assert!(bytes.len().is_multiple_of(2));
     let mut out = Vec::with_capacity(bytes.len());
     for i in (0..bytes.len()).step_by(2) {
         out.push(bytes[i + 1]);
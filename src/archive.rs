@@ -0,0 +1,198 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str;
+
+static ARCHIVE_MAGIC: &[u8] = b"!<arch>\n";
+const HEADER_SIZE: usize = 60;
+
+/// A file stored in an `ar(1)` archive, such as an SDK's static library.
+#[derive(Debug, PartialEq)]
+pub struct ArchiveMember<'a> {
+    pub name: String,
+    pub data: &'a [u8],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveError {
+    pub reason: &'static str,
+}
+
+impl Display for ArchiveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "ArchiveError: {}", self.reason)
+    }
+}
+
+impl Error for ArchiveError {}
+
+fn error<T>(reason: &'static str) -> Result<T, ArchiveError> {
+    Err(ArchiveError { reason })
+}
+
+/// Determines if `bytes` are an `ar` archive.
+pub fn is_archive(bytes: &[u8]) -> bool {
+    bytes.starts_with(ARCHIVE_MAGIC)
+}
+
+// a space padded header field
+fn field(header: &[u8], start: usize, len: usize) -> &str {
+    str::from_utf8(&header[start..(start + len)])
+        .unwrap_or("")
+        .trim_end()
+}
+
+// the name of the member at `offset` in the GNU long name table. names in
+// the table end with "/\n"
+fn long_name(names: &[u8], offset: usize) -> Result<String, ArchiveError> {
+    let Some(names) = names.get(offset..) else {
+        return error("long name offset");
+    };
+    let end = names
+        .iter()
+        .position(|b| *b == b'\n')
+        .unwrap_or(names.len());
+    let name = String::from_utf8_lossy(&names[..end]);
+    Ok(name.trim_end_matches('/').to_string())
+}
+
+/// Lists the members of an `ar` archive in the order they were added.
+///
+/// Both the GNU/SysV (`/` terminated names with a `//` long name table)
+/// and BSD (`#1/<len>` names stored before the data) variants are read.
+/// Symbol tables are skipped. Thin archives are not supported since their
+/// members are not stored in the archive.
+pub fn members(bytes: &[u8]) -> Result<Vec<ArchiveMember<'_>>, ArchiveError> {
+    if !is_archive(bytes) {
+        return error("not an archive");
+    }
+
+    let mut members = Vec::new();
+    let mut long_names: &[u8] = &[];
+    let mut offset = ARCHIVE_MAGIC.len();
+
+    while offset + HEADER_SIZE <= bytes.len() {
+        let header = &bytes[offset..(offset + HEADER_SIZE)];
+        if &header[58..60] != b"`\n" {
+            return error("member header");
+        }
+
+        let raw_name = field(header, 0, 16);
+        let Ok(size) = field(header, 48, 10).parse::<usize>() else {
+            return error("member size");
+        };
+
+        let start = offset + HEADER_SIZE;
+        let end = start + size;
+        if end > bytes.len() {
+            return error("member data");
+        }
+        let mut data = &bytes[start..end];
+
+        // members are aligned to even offsets
+        offset = end + (end & 1);
+
+        let name = if raw_name == "/" || raw_name == "/SYM64/" || raw_name == "__.SYMDEF" {
+            // symbol table
+            continue;
+        } else if raw_name == "//" {
+            long_names = data;
+            continue;
+        } else if let Some(name_len) = raw_name.strip_prefix("#1/") {
+            let Ok(name_len) = name_len.parse::<usize>() else {
+                return error("BSD name length");
+            };
+            if name_len > data.len() {
+                return error("BSD name");
+            }
+            let name = String::from_utf8_lossy(&data[..name_len])
+                .trim_end_matches('\0')
+                .to_string();
+            data = &data[name_len..];
+            if name.starts_with("__.SYMDEF") {
+                continue;
+            }
+            name
+        } else if let Some(name_offset) = raw_name.strip_prefix('/') {
+            let Ok(name_offset) = name_offset.parse::<usize>() else {
+                return error("long name reference");
+            };
+            long_name(long_names, name_offset)?
+        } else {
+            raw_name.trim_end_matches('/').to_string()
+        };
+
+        members.push(ArchiveMember { name, data });
+    }
+
+    Ok(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(name: &str, size: usize) -> Vec<u8> {
+        format!(
+            "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+            name, 0, 0, 0, 644, size
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_members() {
+        let mut bytes = ARCHIVE_MAGIC.to_vec();
+
+        // symbol table
+        bytes.extend(header("/", 4));
+        bytes.extend([0, 0, 0, 0]);
+
+        // long names
+        let names = b"a_very_long_member_name.o/\n";
+        bytes.extend(header("//", names.len()));
+        bytes.extend(names);
+        bytes.push(b'\n');
+
+        bytes.extend(header("short.o/", 3));
+        bytes.extend(b"abc");
+        bytes.push(b'\n');
+
+        bytes.extend(header("/0", 2));
+        bytes.extend(b"de");
+
+        bytes.extend(header("#1/8", 10));
+        bytes.extend(b"bsd.o\0\0\0fg");
+
+        let members = members(&bytes).unwrap();
+        assert_eq!(
+            members,
+            vec![
+                ArchiveMember {
+                    name: "short.o".to_string(),
+                    data: b"abc",
+                },
+                ArchiveMember {
+                    name: "a_very_long_member_name.o".to_string(),
+                    data: b"de",
+                },
+                ArchiveMember {
+                    name: "bsd.o".to_string(),
+                    data: b"fg",
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_not_an_archive() {
+        assert!(!is_archive(b"\x7fELF"));
+        assert!(members(b"\x7fELF").is_err());
+
+        let mut truncated = ARCHIVE_MAGIC.to_vec();
+        truncated.extend(header("short.o/", 30));
+        truncated.extend(b"abc");
+        assert!(members(&truncated).is_err());
+    }
+}
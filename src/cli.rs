@@ -1,6 +1,7 @@
 // SPDX-FileCopyrightText: © 2025 TTKB, LLC
 // SPDX-License-Identifier: BSD-3-CLAUSE
-use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, Parser, Subcommand};
 use clap_num::maybe_hex;
 use std::fs::File;
 use std::io::{self, Write};
@@ -8,8 +9,11 @@ use std::path::PathBuf;
 
 use crate::arch::inspect_bin;
 use crate::elf::inspect_elf;
-use crate::fingerprint::{self, fingerprint};
-use crate::scan::scan;
+use crate::fingerprint::{
+    self, fingerprint_elf, fingerprint_lib, fingerprint_map, fingerprint_splat,
+};
+use crate::map::MapFormat;
+use crate::scan::{scan, Granularity, ScanOptions};
 use crate::Options;
 
 /// Finds common sections of code and provides offsets for well known code segments.
@@ -25,25 +29,81 @@ pub struct App {
 
 #[derive(Debug, Subcommand)]
 enum CLICommand {
-    /// Create a fingerprint file from existing map and elf files
+    /// Create a fingerprint file from existing map and elf files. Without a
+    /// map file, functions are grouped by the source files listed in the elf
+    #[clap(override_usage = "mipsmatch fingerprint [OPTIONS] [MAP] <ELF>")]
     Fingerprint {
-        /// A GNU Map file
-        map: PathBuf,
-        /// An overlay elf file
-        elf: PathBuf,
+        /// The linker which wrote the map file
+        #[clap(long = "map-format", value_enum, default_value_t = MapFormat::Auto)]
+        map_format: MapFormat,
+
+        /// Also fingerprint global labels in code without a symbol type, like
+        /// hand-written assembly functions without `.type` directives
+        #[clap(long = "include-labels")]
+        include_labels: bool,
+
+        /// Also fingerprint the `.data` of each object in a map, masking
+        /// pointers so the data can be found wherever it is linked. Requires
+        /// a map file
+        #[clap(long = "data")]
+        data: bool,
+
+        /// An optional map file, followed by an overlay elf file
+        #[arg(required=true, num_args=1..=2, value_names=["MAP", "ELF"])]
+        files: Vec<PathBuf>,
+    },
+
+    /// Create a fingerprint file from static libraries (.a) and relocatable
+    /// objects (.o). Directories are searched for both
+    FingerprintLib {
+        /// Also fingerprint global labels in code without a symbol type, like
+        /// hand-written assembly functions without `.type` directives
+        #[clap(long = "include-labels")]
+        include_labels: bool,
+
+        #[arg(required=true, num_args=1..)]
+        libs: Vec<PathBuf>,
+    },
+
+    /// Create a fingerprint file from a splat config, its symbol_addrs.txt
+    /// file, and the original binary it splits
+    FingerprintSplat {
+        /// A splat YAML config
+        config: PathBuf,
+        /// The symbol_addrs.txt file used by the config
+        symbol_addrs: PathBuf,
+        /// The original binary
+        bin: PathBuf,
     },
 
     /// Use a fingerprint file to find offsets in a new overlay
     Scan {
-        /// The level match granularity should occur (segment, function)
+        /// The level match granularity should occur (all, segment, function)
         #[clap(short, long, value_enum, default_value_t = Granularity::All)]
         granularity: Granularity,
 
-        /// The location the inspected binary would be loaded in VRAM. Required for
-        /// searching for DATA and RODATA segments
+        /// The location the inspected binary would be loaded in VRAM. Inferred
+        /// from the matched segments when not given. Used to locate RODATA by
+        /// its jump tables and to tell copies of DATA apart
         #[clap(short, long="vram-start", value_parser=maybe_hex::<usize>)]
         vram_start: Option<usize>,
 
+        /// Also report segments where at least this fraction of their
+        /// functions, greater than 0 and at most 1, are found in order
+        #[clap(long = "min-coverage", value_parser=coverage)]
+        min_coverage: Option<f64>,
+
+        /// Also match segments as an ordered list of functions separated by
+        /// up to this many NOP/zero words of alignment padding
+        #[clap(long = "max-padding")]
+        max_padding: Option<usize>,
+
+        /// Search the bin this many bytes at a time. Only one chunk is
+        /// normalized in memory at once
+        #[clap(long = "chunk-size", value_parser=maybe_hex::<usize>,
+               default_value_t = ScanOptions::DEFAULT_CHUNK_SIZE)]
+        chunk_size: usize,
+
         #[arg(required=true, num_args=1..)]
         match_config: Vec<PathBuf>,
         bin: PathBuf,
@@ -56,14 +116,6 @@ enum CLICommand {
     Bin { bin: PathBuf },
 }
 
-#[derive(ValueEnum, Clone, Default, Debug)]
-enum Granularity {
-    #[default]
-    All,
-    Segment,
-    Function,
-}
-
 #[derive(Debug, Args)]
 struct GlobalOpts {
     // /// Verbosity level (can be specified multiple times)
@@ -79,6 +131,18 @@ struct GlobalOpts {
     modulus: u64,
 }
 
+// a `--min-coverage` fraction, greater than 0 and at most 1
+fn coverage(s: &str) -> Result<f64, String> {
+    let coverage = s
+        .parse::<f64>()
+        .map_err(|_| format!("`{}` is not a number", s))?;
+    if coverage > 0.0 && coverage <= 1.0 {
+        Ok(coverage)
+    } else {
+        Err(format!("{} is not greater than 0 and at most 1", s))
+    }
+}
+
 pub fn main() {
     let args = App::parse();
 
@@ -90,16 +154,68 @@ pub fn main() {
     });
 
     match args.command {
-        CLICommand::Fingerprint { map, elf } => {
-            fingerprint(&map, &elf, &mut options);
+        CLICommand::Fingerprint {
+            map_format,
+            include_labels,
+            data,
+            files,
+        } => {
+            options.include_labels = include_labels;
+            options.include_data = data;
+            match files.as_slice() {
+                [_] if data => App::command()
+                    .find_subcommand_mut("fingerprint")
+                    .expect("fingerprint command")
+                    .error(
+                        ErrorKind::MissingRequiredArgument,
+                        "--data requires a map file to group data by object",
+                    )
+                    .exit(),
+                [elf] => fingerprint_elf(elf, &mut options),
+                [map, elf] => fingerprint_map(map, map_format, elf, &mut options),
+                _ => unreachable!("one or two files"),
+            }
+        }
+        CLICommand::FingerprintLib {
+            include_labels,
+            libs,
+        } => {
+            options.include_labels = include_labels;
+            fingerprint_lib(&libs, &mut options);
+        }
+        CLICommand::FingerprintSplat {
+            config,
+            symbol_addrs,
+            bin,
+        } => {
+            if let Err(error) = fingerprint_splat(&config, &symbol_addrs, &bin, &mut options) {
+                App::command()
+                    .find_subcommand_mut("fingerprint-splat")
+                    .expect("fingerprint-splat command")
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!("could not parse {}: {}", config.display(), error),
+                    )
+                    .exit();
+            }
         }
         CLICommand::Scan {
-            granularity: _,
+            granularity,
             vram_start,
+            min_coverage,
+            max_padding,
+            chunk_size,
             match_config,
             bin,
         } => {
-            scan(&match_config, &bin, vram_start, &mut options);
+            let scan_options = ScanOptions {
+                granularity,
+                vram_start,
+                min_coverage,
+                max_padding,
+                chunk_size,
+            };
+            scan(&match_config, &bin, &scan_options, &mut options);
         }
         CLICommand::Elf { elf } => {
             inspect_elf(&elf, &mut options);
@@ -0,0 +1,172 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::arch::mips::{self, Registers, OP_J, OP_JAL, TARGET_MASK};
+use crate::{AddressReference, MIPSFamily};
+
+/// Whether the relocated fields of a match agree on where it was linked.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeltaCheck {
+    /// Every reference moved by the same amount as the rest of the segment.
+    Consistent,
+    /// The references moved by different amounts, so the opcodes line up
+    /// but the code is not the same.
+    Inconsistent,
+    /// The segment has no references to itself, or was not matched as a
+    /// whole.
+    Unchecked,
+}
+
+/// Every address `code`, linked at `vram`, refers to: the targets of `j`
+/// and `jal`, and addresses built with `lui` and `addiu` or `ori`. Each is
+/// keyed by the offset of the instruction which completes it. `vram` only
+/// provides the upper 4 bits of `j` and `jal` targets.
+pub fn address_references(code: &[u8], vram: usize, family: MIPSFamily) -> Vec<AddressReference> {
+    let mut registers = Registers::new();
+    let mut references = Vec::new();
+
+    for (i, word) in code.chunks_exact(4).enumerate() {
+        let instruction = mips::read_word(word, family);
+        let offset = i * 4;
+
+        if let OP_J | OP_JAL = instruction >> 26 {
+            references.push(AddressReference {
+                offset,
+                address: ((vram + offset) & !TARGET_MASK) | mips::jump_target(instruction),
+            });
+        }
+        if let Some(address) = registers.step(instruction) {
+            references.push(AddressReference {
+                offset,
+                address: address as usize,
+            });
+        }
+    }
+
+    references
+}
+
+/// The references of `code`, linked at `vram`, which land within `within`.
+/// These move with the code wherever it is linked.
+pub fn internal_references(
+    code: &[u8],
+    vram: usize,
+    within: Range<usize>,
+    family: MIPSFamily,
+) -> Vec<AddressReference> {
+    address_references(code, vram, family)
+        .into_iter()
+        .filter(|reference| within.contains(&reference.address))
+        .collect()
+}
+
+/// Checks that the references of a segment, recorded from the reference
+/// build, all moved by the same amount in the `code` it was matched to.
+/// Returns how much they moved when they agree.
+///
+/// Only the low 28 bits of each address are compared, since that is all
+/// `j` and `jal` encode.
+pub fn check_delta(
+    references: &[AddressReference],
+    code: &[u8],
+    family: MIPSFamily,
+) -> (DeltaCheck, Option<isize>) {
+    if references.is_empty() {
+        return (DeltaCheck::Unchecked, None);
+    }
+
+    let found = address_references(code, 0, family)
+        .into_iter()
+        .map(|reference| (reference.offset, reference.address))
+        .collect::<HashMap<_, _>>();
+
+    let mut delta = None;
+    for reference in references {
+        let Some(address) = found.get(&reference.offset) else {
+            return (DeltaCheck::Inconsistent, None);
+        };
+        let moved = address.wrapping_sub(reference.address) & TARGET_MASK;
+        if *delta.get_or_insert(moved) != moved {
+            return (DeltaCheck::Inconsistent, None);
+        }
+    }
+
+    // the delta is signed within the 28 bits compared
+    let delta = delta.map(|delta| ((delta << 4) as u32 as i32 >> 4) as isize);
+    (DeltaCheck::Consistent, delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arch::mips::assemble;
+
+    #[test]
+    fn test_check_delta() {
+        let family = MIPSFamily::R3000GTE;
+
+        // a function at 0x80100000 which calls itself, refers to its own
+        // address, and calls out of the segment
+        let code = assemble(&[
+            0x0C040000, // jal   0x80100000
+            0x3C048010, // lui   $a0, 0x8010
+            0x24840000, // addiu $a0, $a0, 0
+            0x0C000100, // jal   0x80000400
+            0x03E00008, // jr    $ra
+        ]);
+        let references = internal_references(&code, 0x80100000, 0x80100000..0x80100014, family);
+        assert_eq!(
+            references,
+            vec![
+                AddressReference {
+                    offset: 0x0,
+                    address: 0x80100000,
+                },
+                AddressReference {
+                    offset: 0x8,
+                    address: 0x80100000,
+                },
+            ]
+        );
+        assert_eq!(
+            check_delta(&references, &code, family),
+            (DeltaCheck::Consistent, Some(0))
+        );
+
+        // the same function linked 0x100 bytes lower, calling out to the
+        // same address
+        let moved = assemble(&[
+            0x0C03FFC0, // jal   0x800FFF00
+            0x3C048010, // lui   $a0, 0x8010
+            0x2484FF00, // addiu $a0, $a0, -0x100
+            0x0C000100, // jal   0x80000400
+            0x03E00008, // jr    $ra
+        ]);
+        assert_eq!(
+            check_delta(&references, &moved, family),
+            (DeltaCheck::Consistent, Some(-0x100))
+        );
+
+        // the call moved, but the address did not
+        let inconsistent = assemble(&[
+            0x0C03FFC0, // jal   0x800FFF00
+            0x3C048010, // lui   $a0, 0x8010
+            0x24840000, // addiu $a0, $a0, 0
+            0x0C000100, // jal   0x80000400
+            0x03E00008, // jr    $ra
+        ]);
+        assert_eq!(
+            check_delta(&references, &inconsistent, family),
+            (DeltaCheck::Inconsistent, None)
+        );
+
+        assert_eq!(
+            check_delta(&[], &code, family),
+            (DeltaCheck::Unchecked, None)
+        );
+    }
+}
@@ -0,0 +1,73 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+use elf::endian::{AnyEndian, EndianParse};
+use elf::ElfBytes;
+use gimli::{EndianSlice, RunTimeEndian, SectionId};
+use std::ops::Range;
+
+/// A DWARF compile unit, along with the addresses of the code compiled
+/// from it.
+#[derive(Debug, PartialEq)]
+pub struct CompileUnit {
+    pub name: String,
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl CompileUnit {
+    pub fn contains(&self, addr: usize) -> bool {
+        self.ranges.iter().any(|range| range.contains(&addr))
+    }
+}
+
+/// Lists the compile units of an ELF file which cover any code. Empty if
+/// the file has no DWARF debug info.
+pub fn compile_units(file: &ElfBytes<AnyEndian>) -> Vec<CompileUnit> {
+    let endian = if file.ehdr.endianness.is_little() {
+        RunTimeEndian::Little
+    } else {
+        RunTimeEndian::Big
+    };
+
+    let load_section = |id: SectionId| -> Result<EndianSlice<RunTimeEndian>, gimli::Error> {
+        let data = match file.section_header_by_name(id.name()) {
+            Ok(Some(shdr)) => file
+                .section_data(&shdr)
+                .map(|(data, _)| data)
+                .unwrap_or(&[]),
+            _ => &[],
+        };
+        Ok(EndianSlice::new(data, endian))
+    };
+
+    let Ok(dwarf) = gimli::Dwarf::load(load_section) else {
+        return Vec::new();
+    };
+
+    let mut units = Vec::new();
+    let mut headers = dwarf.units();
+    while let Ok(Some(header)) = headers.next() {
+        let Ok(unit) = dwarf.unit(header) else {
+            continue;
+        };
+
+        let name = unit
+            .name
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut ranges = Vec::new();
+        if let Ok(mut unit_ranges) = dwarf.unit_ranges(&unit) {
+            while let Ok(Some(range)) = unit_ranges.next() {
+                if range.begin < range.end {
+                    ranges.push((range.begin as usize)..(range.end as usize));
+                }
+            }
+        }
+
+        if !ranges.is_empty() {
+            units.push(CompileUnit { name, ranges });
+        }
+    }
+
+    units
+}
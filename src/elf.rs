@@ -1,14 +1,22 @@
 // SPDX-FileCopyrightText: © 2025 TTKB, LLC
 // SPDX-License-Identifier: BSD-3-CLAUSE
-use crate::map::FunctionEntry;
+use crate::dwarf;
+use crate::map::{FunctionEntry, ObjectMap};
+use crate::mdebug;
 use elf::endian::AnyEndian;
+use elf::file::FileHeader;
 use elf::section::SectionHeader;
+use elf::string_table::StringTable;
 use elf::ElfBytes;
 use elf::{self};
-use std::collections::HashMap;
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::io::Write;
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
+use crate::arch::mips;
+use crate::input::MappedFile;
 use crate::MIPSFamily;
 use crate::Options;
 
@@ -17,11 +25,15 @@ const EF_MIPS_MACH_5900: u32 = 0x00920000;
 /// Determines the MIPS family from a given ELF file. This is focused on the
 /// PS1, PS2, PSP, and N64 architectures, specifically.
 pub fn mips_family(elf_path: &Path) -> Option<MIPSFamily> {
-    let file_data = std::fs::read(elf_path).expect("Could not read file.");
+    let file_data = MappedFile::open(elf_path).expect("Could not read file.");
     let slice = file_data.as_slice();
     let file = ElfBytes::<AnyEndian>::minimal_parse(slice).expect("Parse elf file");
 
-    let header = file.ehdr;
+    family_for_header(&file.ehdr)
+}
+
+/// Determines the MIPS family from the header of an ELF file.
+pub fn family_for_header(header: &FileHeader<AnyEndian>) -> Option<MIPSFamily> {
     let flags = header.e_flags;
 
     if header.e_machine == elf::abi::EM_MIPS {
@@ -37,56 +49,340 @@ pub fn mips_family(elf_path: &Path) -> Option<MIPSFamily> {
         }
     }
 
-    return None;
+    None
 }
 
 pub fn align(offset: usize, alignment: usize) -> usize {
     (offset + alignment - 1) & !(alignment - 1)
 }
 
-pub fn bin_data(elf_path: &Path) -> HashMap<usize, Vec<u8>> {
-    let file_data = std::fs::read(elf_path).expect("Could not read file.");
+// executable sections which contain code
+fn is_code_section(shdr: &SectionHeader, strtab: &StringTable) -> bool {
+    if shdr.sh_type != elf::abi::SHT_PROGBITS
+        || (shdr.sh_flags as u32 & elf::abi::SHF_EXECINSTR) != elf::abi::SHF_EXECINSTR
+    {
+        return false;
+    }
+
+    // n.b.! this check is probably redundant, but headers from GCC
+    //       and MetroWorks are marked as `PROGBITS`, but don't
+    //       have an executable flag. In case they somehow make it
+    //       through, exclude them as well.
+    let section_name = strtab.get(shdr.sh_name as usize).unwrap_or("");
+    section_name != ".mwo_header" && section_name != ".header"
+}
+
+/// A code section of a linked ELF.
+pub struct SectionData {
+    /// The index of the section, which [`relocation_masks`] are keyed by.
+    pub index: usize,
+    pub vram: usize,
+    /// The load address of the section. Overlays which share the same
+    /// addresses are loaded from different places.
+    pub vrom: usize,
+    /// The offset of the section in the ELF file.
+    pub offset: usize,
+    pub data: Vec<u8>,
+}
+
+impl SectionData {
+    pub fn contains_vram(&self, vram: usize) -> bool {
+        vram >= self.vram && vram < self.vram + self.data.len()
+    }
+
+    pub fn contains_vrom(&self, vrom: usize) -> bool {
+        vrom >= self.vrom && vrom < self.vrom + self.data.len()
+    }
+}
+
+// the load address of a section, from the program header which loads it.
+// sections which are not loaded by a program header load at their address
+fn load_address(file: &ElfBytes<AnyEndian>, shdr: &SectionHeader) -> usize {
+    file.segments()
+        .and_then(|phdrs| {
+            phdrs.iter().find(|phdr| {
+                phdr.p_type == elf::abi::PT_LOAD
+                    && shdr.sh_offset >= phdr.p_offset
+                    && shdr.sh_offset < phdr.p_offset + phdr.p_filesz
+            })
+        })
+        .map(|phdr| (phdr.p_paddr + shdr.sh_offset - phdr.p_offset) as usize)
+        .unwrap_or(shdr.sh_addr as usize)
+}
+
+// initialized sections which can be written to, like `.data`
+fn is_data_section(shdr: &SectionHeader) -> bool {
+    let flags = shdr.sh_flags as u32;
+    shdr.sh_type == elf::abi::SHT_PROGBITS
+        && (flags & elf::abi::SHF_ALLOC) != 0
+        && (flags & elf::abi::SHF_WRITE) != 0
+        && (flags & elf::abi::SHF_EXECINSTR) == 0
+}
+
+fn is_rodata_section(shdr: &SectionHeader) -> bool {
+    let flags = shdr.sh_flags as u32;
+    shdr.sh_type == elf::abi::SHT_PROGBITS
+        && (flags & elf::abi::SHF_ALLOC) != 0
+        && (flags & (elf::abi::SHF_WRITE | elf::abi::SHF_EXECINSTR)) == 0
+}
+
+// the sections of a linked ELF in `sections`, in section order
+fn section_data(
+    file: &ElfBytes<AnyEndian>,
+    sections: HashMap<usize, SectionHeader>,
+) -> Vec<SectionData> {
+    let mut sections = sections.into_iter().collect::<Vec<_>>();
+    sections.sort_by_key(|(index, _)| *index);
+
+    sections
+        .into_iter()
+        .map(|(index, shdr)| {
+            let (section_data, _) = file.section_data(&shdr).expect("section data");
+            SectionData {
+                index,
+                vram: shdr.sh_addr as usize,
+                vrom: load_address(file, &shdr),
+                offset: shdr.sh_offset as usize,
+                data: section_data.to_vec(),
+            }
+        })
+        .collect()
+}
+
+/// The code sections of a linked ELF, in section order.
+pub fn bin_data(elf_path: &Path) -> Vec<SectionData> {
+    let file_data = MappedFile::open(elf_path).expect("Could not read file.");
     let slice = file_data.as_slice();
     let file = ElfBytes::<AnyEndian>::minimal_parse(slice).expect("Parse elf file");
-    let (shdrs_opt, strtab_opt) = file
-        .section_headers_with_strtab()
-        .expect("shdrs offsets should be valid");
 
-    let (shdrs, strtab) = (
-        shdrs_opt.expect("Should have shdrs"),
-        strtab_opt.expect("Should have strtab"),
-    );
+    section_data(&file, code_sections(&file))
+}
 
-    // Parse the shdrs and collect them into a map keyed on their zero-copied name
-    let program_section_headers: Vec<SectionHeader> = shdrs
+/// The initialized, writable data sections of a linked ELF, like `.data`,
+/// in section order.
+pub fn data_sections(elf_path: &Path) -> Vec<SectionData> {
+    let file_data = MappedFile::open(elf_path).expect("Could not read file.");
+    let slice = file_data.as_slice();
+    let file = ElfBytes::<AnyEndian>::minimal_parse(slice).expect("Parse elf file");
+
+    let sections = match file.section_headers() {
+        Some(shdrs) => shdrs
+            .iter()
+            .enumerate()
+            .filter(|(_, shdr)| is_data_section(shdr))
+            .collect(),
+        None => HashMap::new(),
+    };
+    section_data(&file, sections)
+}
+
+/// The read-only data sections of a linked ELF, like `.rodata`, in section
+/// order.
+pub fn rodata_sections(elf_path: &Path) -> Vec<SectionData> {
+    let file_data = MappedFile::open(elf_path).expect("Could not read file.");
+    let slice = file_data.as_slice();
+    let file = ElfBytes::<AnyEndian>::minimal_parse(slice).expect("Parse elf file");
+
+    let sections = match file.section_headers() {
+        Some(shdrs) => shdrs
+            .iter()
+            .enumerate()
+            .filter(|(_, shdr)| is_rodata_section(shdr))
+            .collect(),
+        None => HashMap::new(),
+    };
+    section_data(&file, sections)
+}
+
+/// The addresses spanned by the allocated sections of an ELF, which any
+/// pointer into it falls within.
+pub fn vram_range(elf_path: &Path) -> Range<usize> {
+    let file_data = MappedFile::open(elf_path).expect("Could not read file.");
+    let slice = file_data.as_slice();
+    let file = ElfBytes::<AnyEndian>::minimal_parse(slice).expect("Parse elf file");
+
+    let Some(shdrs) = file.section_headers() else {
+        return 0..0;
+    };
+    shdrs
         .iter()
-        .filter(|shdr| shdr.sh_type == elf::abi::SHT_PROGBITS)
-        .filter(|shdr| (shdr.sh_flags as u32 & elf::abi::SHF_EXECINSTR) == elf::abi::SHF_EXECINSTR)
-        .filter(|shdr| {
-            // n.b.! this check is probably redundant, but headers from GCC
-            //       and MetroWorks are marked as `PROGBITS`, but don't
-            //       have an executable flag. In case they somehow make it
-            //       through, exclude them as well.
-            let section_name = strtab.get(shdr.sh_name as usize).unwrap();
-            section_name != ".mwo_header" && section_name != ".header"
+        .filter(|shdr| (shdr.sh_flags as u32 & elf::abi::SHF_ALLOC) != 0 && shdr.sh_size > 0)
+        .map(|shdr| (shdr.sh_addr as usize)..((shdr.sh_addr + shdr.sh_size) as usize))
+        .reduce(|a, b| cmp::min(a.start, b.start)..cmp::max(a.end, b.end))
+        .unwrap_or(0..0)
+}
+
+/// The `STT_OBJECT` symbols of the data sections of an ELF file, with
+/// their load addresses as offsets, like [`function_symbols`].
+pub fn data_symbols(elf_path: &Path) -> Vec<FunctionEntry> {
+    let file_data = MappedFile::open(elf_path).expect("Could not read file.");
+    let slice = file_data.as_slice();
+    let file = ElfBytes::<AnyEndian>::minimal_parse(slice).expect("valid elf file");
+
+    let (Some(shdrs), Ok(Some((symtab, strtab)))) = (file.section_headers(), file.symbol_table())
+    else {
+        return Vec::new();
+    };
+
+    symtab
+        .iter()
+        .filter(|symbol| symbol.st_symtype() == elf::abi::STT_OBJECT)
+        .filter_map(|symbol| {
+            let shdr = shdrs.get(symbol.st_shndx as usize).ok()?;
+            if !is_data_section(&shdr) {
+                return None;
+            }
+            let vram = symbol.st_value as usize;
+            Some(FunctionEntry {
+                name: strtab.get(symbol.st_name as usize).ok()?.to_string(),
+                offset: load_address(&file, &shdr) + vram - shdr.sh_addr as usize,
+                vram,
+                size: symbol.st_size as usize,
+                size_inferred: false,
+            })
+        })
+        .collect()
+}
+
+/// Collects the relocations which apply to each section of `file`. The
+/// result is keyed by the index of the relocated section, and maps the
+/// offset of each relocated word within that section to the bits the
+/// relocation fills in.
+pub fn section_relocation_masks(
+    file: &ElfBytes<AnyEndian>,
+) -> HashMap<usize, BTreeMap<usize, u32>> {
+    let mut masks: HashMap<usize, BTreeMap<usize, u32>> = HashMap::new();
+    let Some(shdrs) = file.section_headers() else {
+        return masks;
+    };
+
+    // linked objects record relocation offsets as addresses, relocatable
+    // objects as offsets into the relocated section
+    let is_relocatable = file.ehdr.e_type == elf::abi::ET_REL;
+
+    for shdr in shdrs.iter() {
+        let relocations = match shdr.sh_type {
+            elf::abi::SHT_REL => file
+                .section_data_as_rels(&shdr)
+                .expect("relocation section")
+                .map(|rel| (rel.r_offset, rel.r_type))
+                .collect::<Vec<_>>(),
+            elf::abi::SHT_RELA => file
+                .section_data_as_relas(&shdr)
+                .expect("relocation section")
+                .map(|rela| (rela.r_offset, rela.r_type))
+                .collect::<Vec<_>>(),
+            _ => continue,
+        };
+
+        let target = shdr.sh_info as usize;
+        let Ok(target_shdr) = shdrs.get(target) else {
+            continue;
+        };
+        let base = if is_relocatable {
+            0
+        } else {
+            target_shdr.sh_addr
+        };
+
+        let section_masks = masks.entry(target).or_default();
+        for (r_offset, r_type) in relocations {
+            let offset = r_offset.wrapping_sub(base) as usize;
+            *section_masks.entry(offset & !3).or_default() |= mips::relocation_mask(r_type);
+        }
+    }
+
+    masks
+}
+
+/// The relocations of a linked ELF keyed by section index, like
+/// [`SectionData`]. Empty if the ELF was linked without relocations.
+pub fn relocation_masks(elf_path: &Path) -> HashMap<usize, BTreeMap<usize, u32>> {
+    let file_data = MappedFile::open(elf_path).expect("Could not read file.");
+    let slice = file_data.as_slice();
+    let file = ElfBytes::<AnyEndian>::minimal_parse(slice).expect("Parse elf file");
+
+    section_relocation_masks(&file)
+}
+
+/// The code of a relocatable object (`.o`), with its executable sections
+/// placed one after another the way a linker would place them.
+pub struct ObjectCode {
+    pub family: Option<MIPSFamily>,
+    pub bytes: Vec<u8>,
+    /// Function symbols, with `vram` and `offset` relative to `bytes`.
+    pub functions: Vec<FunctionEntry>,
+    /// The relocated bits of each word of `bytes`.
+    pub relocation_masks: BTreeMap<usize, u32>,
+}
+
+/// Lays out the code of a relocatable MIPS object. Returns `None` when
+/// `data` is not a relocatable MIPS ELF file. Functions are read like
+/// [`function_symbols`].
+pub fn object_code(data: &[u8], include_labels: bool) -> Option<ObjectCode> {
+    let file = ElfBytes::<AnyEndian>::minimal_parse(data).ok()?;
+    if file.ehdr.e_type != elf::abi::ET_REL || file.ehdr.e_machine != elf::abi::EM_MIPS {
+        return None;
+    }
+
+    let (shdrs, strtab) = match file.section_headers_with_strtab().ok()? {
+        (Some(shdrs), Some(strtab)) => (shdrs, strtab),
+        _ => return None,
+    };
+
+    // the offset each code section is placed at, keyed by section index
+    let mut bytes = Vec::new();
+    let mut bases = HashMap::new();
+    for (index, shdr) in shdrs.iter().enumerate() {
+        if !is_code_section(&shdr, &strtab) {
+            continue;
+        }
+        let (section_data, _) = file.section_data(&shdr).ok()?;
+        let base = align(bytes.len(), cmp::max(shdr.sh_addralign as usize, 1));
+        bytes.resize(base, 0);
+        bytes.extend_from_slice(section_data);
+        bases.insert(index, base);
+    }
+
+    let code_sections = code_sections(&file);
+    let mut functions = symbol_functions(&file, &code_sections, include_labels);
+    infer_sizes(
+        &mut functions,
+        &symbol_boundaries(&file, &code_sections),
+        &code_sections,
+    );
+    let mut functions = functions
+        .into_iter()
+        .filter_map(|(shndx, mut function)| {
+            let base = bases.get(&shndx)?;
+            if function.size == 0 {
+                return None;
+            }
+            function.offset += base;
+            function.vram += base;
+            Some(function)
         })
-        // .inspect(|shdr| println!("found section {:?}", shdr))
-        .collect();
-
-    // TODO: determine capacity first
-
-    let mut data = HashMap::new();
-    for program_section_header in program_section_headers {
-        let (section_data, _) = file
-            .section_data(&program_section_header)
-            .expect("section data");
-        data.insert(
-            program_section_header.sh_addr as usize,
-            section_data.to_vec(),
+        .collect::<Vec<_>>();
+    functions.sort_by_key(|function| function.offset);
+
+    let mut relocation_masks = BTreeMap::new();
+    for (index, masks) in section_relocation_masks(&file) {
+        let Some(base) = bases.get(&index) else {
+            continue;
+        };
+        relocation_masks.extend(
+            masks
+                .into_iter()
+                .map(|(offset, mask)| (base + offset, mask)),
         );
     }
 
-    data
+    Some(ObjectCode {
+        family: family_for_header(&file.ehdr),
+        bytes,
+        functions,
+        relocation_masks,
+    })
 }
 
 pub struct Symbol {
@@ -97,30 +393,420 @@ pub struct Symbol {
     pub align: Option<u64>,
 }
 
-pub fn function_symbols(elf_path: &Path) -> Vec<FunctionEntry> {
-    let file_data = std::fs::read(elf_path).expect("Could not read file.");
+/// The functions of an ELF file, from its symbol table and `.mdebug`
+/// procedure descriptors. Global `STT_NOTYPE` labels in code are included
+/// as functions when `include_labels` is set. Functions without a size
+/// extend to the next symbol or the end of their section, and are marked
+/// `size_inferred`. Function offsets are load addresses.
+pub fn function_symbols(elf_path: &Path, include_labels: bool) -> Vec<FunctionEntry> {
+    let file_data = MappedFile::open(elf_path).expect("Could not read file.");
     let slice = file_data.as_slice();
     let file = ElfBytes::<AnyEndian>::minimal_parse(slice).expect("valid elf file");
 
-    let (symtab, strtab) = file
-        .symbol_table()
-        .expect("expected a symbol table")
-        .expect("symtab");
+    let code_sections = code_sections(&file);
+    let mut functions = symbol_functions(&file, &code_sections, include_labels);
+
+    // static procedures are often only listed in `.mdebug`
+    for source in mdebug::source_files(&file) {
+        for function in mdebug_functions(&source) {
+            if functions.iter().any(|(_, f)| f.vram == function.vram) {
+                continue;
+            }
+            if let Some(shndx) = code_section_for(&code_sections, function.vram) {
+                functions.push((shndx, function));
+            }
+        }
+    }
+
+    infer_sizes(
+        &mut functions,
+        &symbol_boundaries(&file, &code_sections),
+        &code_sections,
+    );
+
+    // offsets are load addresses, which tell apart overlays sharing the
+    // same addresses
+    functions
+        .into_iter()
+        .map(|(shndx, mut function)| {
+            if let Some(shdr) = code_sections.get(&shndx) {
+                function.offset = load_address(&file, shdr) + function.vram - shdr.sh_addr as usize;
+            }
+            function
+        })
+        .collect()
+}
+
+// the code sections of `file`, keyed by section index
+fn code_sections(file: &ElfBytes<AnyEndian>) -> HashMap<usize, SectionHeader> {
+    match file.section_headers_with_strtab() {
+        Ok((Some(shdrs), Some(strtab))) => shdrs
+            .iter()
+            .enumerate()
+            .filter(|(_, shdr)| is_code_section(shdr, &strtab))
+            .collect(),
+        _ => HashMap::new(),
+    }
+}
+
+// a global label without a type, like the functions of hand-written
+// assembly without `.type` directives
+fn is_label(symbol: &elf::symbol::Symbol, name: &str) -> bool {
+    symbol.st_symtype() == elf::abi::STT_NOTYPE
+        && symbol.st_bind() != elf::abi::STB_LOCAL
+        && !name.is_empty()
+}
+
+// symbols which end the function before them. local labels are often
+// branch targets within a function, so they are not
+fn is_boundary(symbol: &elf::symbol::Symbol, name: &str) -> bool {
+    match symbol.st_symtype() {
+        elf::abi::STT_FUNC | elf::abi::STT_OBJECT => true,
+        elf::abi::STT_NOTYPE => is_label(symbol, name),
+        _ => false,
+    }
+}
+
+// (section, address) of every symbol which can end a function
+fn symbol_boundaries(
+    file: &ElfBytes<AnyEndian>,
+    code_sections: &HashMap<usize, SectionHeader>,
+) -> BTreeSet<(usize, usize)> {
+    let Ok(Some((symtab, strtab))) = file.symbol_table() else {
+        return BTreeSet::new();
+    };
 
     symtab
         .iter()
-        .filter(|s| s.st_symtype() == elf::abi::STT_FUNC)
-        .map(|s| FunctionEntry {
-            name: strtab.get(s.st_name as usize).unwrap().to_string(),
-            offset: 0,
-            vram: s.st_value as usize,
-            size: s.st_size as usize,
+        .filter(|symbol| code_sections.contains_key(&(symbol.st_shndx as usize)))
+        .filter(|symbol| is_boundary(symbol, strtab.get(symbol.st_name as usize).unwrap_or("")))
+        .map(|symbol| (symbol.st_shndx as usize, symbol.st_value as usize))
+        .collect()
+}
+
+// the `STT_FUNC` symbols of `file`, and labels in code sections when
+// `include_labels` is set, along with the index of their section. sizes
+// are not inferred
+fn symbol_functions(
+    file: &ElfBytes<AnyEndian>,
+    code_sections: &HashMap<usize, SectionHeader>,
+    include_labels: bool,
+) -> Vec<(usize, FunctionEntry)> {
+    let Ok(Some((symtab, strtab))) = file.symbol_table() else {
+        return Vec::new();
+    };
+
+    symtab
+        .iter()
+        .filter_map(|symbol| {
+            let name = strtab.get(symbol.st_name as usize).unwrap_or("");
+            let shndx = symbol.st_shndx as usize;
+            let is_function = symbol.st_symtype() == elf::abi::STT_FUNC
+                || (include_labels
+                    && code_sections.contains_key(&shndx)
+                    && is_label(&symbol, name));
+            if !is_function {
+                return None;
+            }
+            Some((
+                shndx,
+                FunctionEntry {
+                    name: name.to_string(),
+                    offset: symbol.st_value as usize,
+                    vram: symbol.st_value as usize,
+                    size: symbol.st_size as usize,
+                    size_inferred: false,
+                },
+            ))
+        })
+        .collect()
+}
+
+// gives functions without a size the extent up to the next function or
+// boundary in their section, or up to the end of the section
+fn infer_sizes(
+    functions: &mut [(usize, FunctionEntry)],
+    boundaries: &BTreeSet<(usize, usize)>,
+    code_sections: &HashMap<usize, SectionHeader>,
+) {
+    let mut boundaries = boundaries.clone();
+    boundaries.extend(functions.iter().map(|(shndx, f)| (*shndx, f.vram)));
+
+    for (shndx, function) in functions.iter_mut() {
+        if function.size != 0 {
+            continue;
+        }
+        let Some(shdr) = code_sections.get(shndx) else {
+            continue;
+        };
+        let section_end = (shdr.sh_addr + shdr.sh_size) as usize;
+        let end = boundaries
+            .range((*shndx, function.vram + 1)..=(*shndx, usize::MAX))
+            .next()
+            .map_or(section_end, |(_, next)| cmp::min(*next, section_end));
+        if end > function.vram {
+            function.size = end - function.vram;
+            function.size_inferred = true;
+        }
+    }
+}
+
+// the object a source file, from a `STT_FILE` symbol or compile unit, was
+// compiled to
+fn object_for_source(source: &str) -> String {
+    PathBuf::from(source)
+        .with_extension("o")
+        .to_str()
+        .expect("object name")
+        .to_string()
+}
+
+// local symbols which mark the start of an object's code
+fn is_object_start(symtype: u8, name: &str) -> bool {
+    symtype == elf::abi::STT_FUNC || name == "gcc2_compiled." || name.starts_with("__gnu_compiled_")
+}
+
+// an `STT_FILE` symbol, placed by the lowest local function (or marker)
+// which follows it. `end` is past the last of its local symbols
+struct SourceFile {
+    name: String,
+    shndx: Option<usize>,
+    start: u64,
+    end: u64,
+}
+
+impl SourceFile {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            shndx: None,
+            start: 0,
+            end: 0,
+        }
+    }
+}
+
+// (section, source file) of each group of functions
+type Groups = Vec<((usize, String), Vec<FunctionEntry>)>;
+
+// adds `function` to the last group if it is from the same section and
+// source, otherwise starts a new group
+fn push_grouped(groups: &mut Groups, shndx: usize, source: &str, function: FunctionEntry) {
+    match groups.last_mut() {
+        Some(((last_shndx, last_source), entries))
+            if *last_shndx == shndx && last_source == source =>
+        {
+            entries.push(function)
+        }
+        _ => groups.push(((shndx, source.to_string()), vec![function])),
+    }
+}
+
+fn segments_for_groups(
+    file: &ElfBytes<AnyEndian>,
+    groups: Groups,
+    code_sections: &HashMap<usize, SectionHeader>,
+) -> Vec<ObjectMap> {
+    groups
+        .into_iter()
+        .map(|((shndx, source), mut text_symbols)| {
+            let shdr = &code_sections[&shndx];
+            let section_vrom = load_address(file, shdr);
+            let vrom = |vram: usize| section_vrom + vram - shdr.sh_addr as usize;
+
+            let vram = text_symbols.first().expect("grouped function").vram;
+            let end = text_symbols
+                .iter()
+                .map(|function| function.vram + function.size)
+                .max()
+                .expect("grouped function");
+            for function in text_symbols.iter_mut() {
+                function.offset = vrom(function.vram);
+            }
+            ObjectMap {
+                object: object_for_source(&source),
+                offset: vrom(vram),
+                vram,
+                vrom: section_vrom,
+                size: end - vram,
+                rodata: None,
+                text_symbols,
+            }
         })
         .collect()
 }
 
+// the index of the first code section containing `vram`
+fn code_section_for(code_sections: &HashMap<usize, SectionHeader>, vram: usize) -> Option<usize> {
+    code_sections
+        .iter()
+        .filter(|(_, shdr)| {
+            let start = shdr.sh_addr as usize;
+            vram >= start && vram < start + shdr.sh_size as usize
+        })
+        .map(|(index, _)| *index)
+        .min()
+}
+
+// the procedures of `.mdebug` file descriptors as functions. procedures
+// whose end symbol records no size are given a size of zero, to be
+// inferred
+fn mdebug_functions(source: &mdebug::SourceFile) -> impl Iterator<Item = FunctionEntry> + '_ {
+    source.procedures.iter().map(|procedure| FunctionEntry {
+        name: procedure.name.clone(),
+        offset: procedure.vram,
+        vram: procedure.vram,
+        size: procedure.size.unwrap_or(0),
+        size_inferred: false,
+    })
+}
+
+/// Groups the functions of a linked ELF into segments without a map file.
+///
+/// `.mdebug` file descriptors list the procedures of each source file,
+/// statics included, and are used when present. Otherwise, local symbols
+/// follow the `STT_FILE` symbol of the source file they were compiled
+/// from. Each file starts at its lowest local function (or GCC
+/// `gcc2_compiled.` marker) and extends to the start of the next file, so
+/// global functions are grouped by address. Files with no local functions
+/// cannot be placed this way. When there are any, functions past the last
+/// local symbol of the file before them are grouped by the DWARF compile
+/// unit which contains them, or are each given a segment of their own,
+/// named after the function. When there are no `STT_FILE` symbols, DWARF
+/// compile unit ranges are used instead. Functions are read like
+/// [`function_symbols`].
+pub fn file_segments(elf_path: &Path, include_labels: bool) -> Vec<ObjectMap> {
+    let file_data = MappedFile::open(elf_path).expect("Could not read file.");
+    let slice = file_data.as_slice();
+    let file = ElfBytes::<AnyEndian>::minimal_parse(slice).expect("valid elf file");
+
+    let code_sections = code_sections(&file);
+    let boundaries = symbol_boundaries(&file, &code_sections);
+    let mut groups = Groups::new();
+
+    let source_files = mdebug::source_files(&file);
+    if source_files
+        .iter()
+        .any(|source| !source.procedures.is_empty())
+    {
+        // (section, function) along with the source file of each
+        let mut functions = Vec::new();
+        let mut sources = Vec::new();
+        for source in source_files.iter() {
+            for function in mdebug_functions(source) {
+                if let Some(shndx) = code_section_for(&code_sections, function.vram) {
+                    functions.push((shndx, function));
+                    sources.push(&source.name);
+                }
+            }
+        }
+        infer_sizes(&mut functions, &boundaries, &code_sections);
+
+        for ((shndx, function), source) in functions.into_iter().zip(sources) {
+            push_grouped(&mut groups, shndx, source, function);
+        }
+        return segments_for_groups(&file, groups, &code_sections);
+    }
+
+    let Ok(Some((symtab, strtab))) = file.symbol_table() else {
+        return Vec::new();
+    };
+
+    let mut functions = symbol_functions(&file, &code_sections, include_labels)
+        .into_iter()
+        .filter(|(shndx, _)| code_sections.contains_key(shndx))
+        .collect::<Vec<_>>();
+    infer_sizes(&mut functions, &boundaries, &code_sections);
+    functions.sort_by_key(|(shndx, function)| (*shndx, function.vram));
+
+    let mut files: Vec<SourceFile> = Vec::new();
+    let mut current: Option<SourceFile> = None;
+    // files which no local symbol places
+    let mut unanchored = 0;
+
+    for symbol in symtab.iter() {
+        let name = strtab.get(symbol.st_name as usize).unwrap_or("");
+        let shndx = symbol.st_shndx as usize;
+
+        if symbol.st_symtype() == elf::abi::STT_FILE {
+            match current.take() {
+                Some(file) if file.shndx.is_some() => files.push(file),
+                Some(_) => unanchored += 1,
+                None => (),
+            }
+            current = Some(SourceFile::new(name));
+            continue;
+        }
+
+        let Some(file) = current.as_mut() else {
+            continue;
+        };
+        if !code_sections.contains_key(&shndx) || symbol.st_bind() != elf::abi::STB_LOCAL {
+            continue;
+        }
+        if is_object_start(symbol.st_symtype(), name)
+            && file.shndx.is_none_or(|_| symbol.st_value < file.start)
+        {
+            file.shndx = Some(shndx);
+            file.start = symbol.st_value;
+        }
+        if matches!(
+            symbol.st_symtype(),
+            elf::abi::STT_FUNC | elf::abi::STT_NOTYPE
+        ) {
+            file.end = cmp::max(file.end, symbol.st_value + cmp::max(symbol.st_size, 1));
+        }
+    }
+    match current {
+        Some(file) if file.shndx.is_some() => files.push(file),
+        Some(_) => unanchored += 1,
+        None => (),
+    }
+    files.sort_by_key(|file| (file.shndx, file.start));
+
+    let units = dwarf::compile_units(&file);
+    if files.is_empty() {
+        for (shndx, function) in functions {
+            if let Some(unit) = units.iter().find(|unit| unit.contains(function.vram)) {
+                push_grouped(&mut groups, shndx, &unit.name, function);
+            }
+        }
+        return segments_for_groups(&file, groups, &code_sections);
+    }
+
+    for (shndx, function) in functions {
+        let vram = function.vram as u64;
+        let Some(owner) = files
+            .iter()
+            .rev()
+            .find(|file| file.shndx == Some(shndx) && file.start <= vram)
+        else {
+            continue;
+        };
+
+        // a function past the local symbols of the file before it may be
+        // from a file with only global functions
+        if unanchored == 0 || vram < owner.end {
+            push_grouped(&mut groups, shndx, &owner.name, function);
+            continue;
+        }
+        match units.iter().find(|unit| unit.contains(function.vram)) {
+            Some(unit) if unit.contains(owner.start as usize) => {
+                push_grouped(&mut groups, shndx, &owner.name, function)
+            }
+            Some(unit) => push_grouped(&mut groups, shndx, &unit.name, function),
+            None => {
+                let name = function.name.clone();
+                groups.push(((shndx, name), vec![function]));
+            }
+        }
+    }
+
+    segments_for_groups(&file, groups, &code_sections)
+}
+
 pub fn inspect_elf<W: Write>(elf_file: &Path, _options: &mut Options<W>) {
-    let file_data = std::fs::read(elf_file).expect("Could not read file.");
+    let file_data = MappedFile::open(elf_file).expect("Could not read file.");
     let slice = file_data.as_slice();
     let file = ElfBytes::<AnyEndian>::minimal_parse(slice).expect("Open test1");
     let (shdrs_opt, strtab_opt) = file
@@ -4,19 +4,28 @@ use serde::de::{Deserializer, Error as DE, Unexpected, Visitor};
 use serde::{Deserialize, Serialize, Serializer};
 
 use std::cmp;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
 use std::fmt::{self, Debug, Display, Formatter};
+use std::fs;
 use std::hash::Hasher;
 use std::io::Write;
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use crate::arch::mips;
-use crate::map::{read_segments, ObjectMap};
+use crate::archive;
+use crate::delta;
+use crate::input::MappedFile;
+use crate::map::{read_segments, MapFormat, ObjectMap};
 use crate::rk::RabinKarpMIPSHasher;
+use crate::splat;
 use crate::SerializeToYAML;
-use crate::{FunctionSignature, Options, RODataSignature, RODataSignatureType, SegmentSignature};
+use crate::{
+    CallSite, DataSignature, FunctionSignature, MIPSFamily, Options, RODataSignature,
+    RODataSignatureType, RelocationMask, SegmentSignature,
+};
 
 use crate::elf::{self};
 
@@ -259,7 +268,7 @@ impl FromStr for FingerprintV0 {
             });
         }
 
-        let size_part = u64::from_str_radix(parts.get(0).expect("size"), 10);
+        let size_part = parts.first().expect("size").parse::<u64>();
         let size = match size_part {
             Ok(s) => s,
             Err(_) => {
@@ -283,28 +292,27 @@ impl FromStr for FingerprintV0 {
             return Ok(Self::new(size, hash));
         }
 
-        let modulus_part = u64::from_str_radix(parts.get(2).expect("modulus"), 10);
+        let modulus_part = parts.get(2).expect("modulus").parse::<u64>();
         match modulus_part {
             Ok(m) => Ok(Self::new_with_modulus(size, hash, m)),
-            Err(_) => {
-                return Err(FingerprintError {
-                    kind: FingerprintErrorKind::ParseIntError,
-                })
-            }
+            Err(_) => Err(FingerprintError {
+                kind: FingerprintErrorKind::ParseIntError,
+            }),
         }
     }
 }
 
-impl ToString for FingerprintV0 {
-    fn to_string(&self) -> String {
+impl Display for FingerprintV0 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self.modulus {
             Some(m) => {
-                format!(
+                write!(
+                    f,
                     "{}{}:{:x}:{}",
                     FINGERPRINT_V0_PREFIX, self.size, self.hash, m
                 )
             }
-            None => format!("{}{}:{:x}", FINGERPRINT_V0_PREFIX, self.size, self.hash),
+            None => write!(f, "{}{}:{:x}", FINGERPRINT_V0_PREFIX, self.size, self.hash),
         }
     }
 }
@@ -336,88 +344,254 @@ fn sig_for_range<W: Write>(bytes: &[u8], options: &Options<W>) -> Fingerprint {
     ))
 }
 
+/// Calculates a digest of the normalized instructions in `bytes`.
+///
+/// The digest is a 64-bit FNV-1a hash which is independent of the
+/// Rabin-Karp fingerprint. A fingerprint match is only accepted when
+/// the digest of the candidate matches as well.
+pub fn digest_for_range(bytes: &[u8], family: MIPSFamily) -> u64 {
+    let words = bytes
+        .chunks(4)
+        .map(|ins| mips::bytes_to_normalized_instruction(ins, family))
+        .collect::<Vec<u32>>();
+    digest_for_words(&words)
+}
+
+/// Calculates the digest of instructions which are already normalized.
+pub fn digest_for_words(words: &[u32]) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xCBF29CE484222325;
+    const FNV_PRIME: u64 = 0x00000100000001B3;
+
+    words
+        .iter()
+        .flat_map(|ins| ins.to_le_bytes())
+        .fold(FNV_OFFSET_BASIS, |hash, b| {
+            (hash ^ b as u64).wrapping_mul(FNV_PRIME)
+        })
+}
+
+/// Calculates a digest of the instructions in `bytes` where only the
+/// fields filled in by `relocations` are masked. Every other bit must
+/// match exactly, including constants and stack offsets that opcode based
+/// normalization discards.
+pub fn digest_for_relocated_range(
+    bytes: &[u8],
+    relocations: &[RelocationMask],
+    family: MIPSFamily,
+) -> u64 {
+    let mut words = bytes
+        .chunks(4)
+        .map(|ins| mips::read_word(ins, family))
+        .collect::<Vec<u32>>();
+    for relocation in relocations {
+        if let Some(word) = words.get_mut(relocation.offset / 4) {
+            *word &= !relocation.mask;
+        }
+    }
+    digest_for_words(&words)
+}
+
+// the relocations which apply to the `size` bytes at `start`, relative to
+// `start`
+fn relocations_for_range(
+    masks: &BTreeMap<usize, u32>,
+    start: usize,
+    size: usize,
+) -> Vec<RelocationMask> {
+    masks
+        .range(start..(start + size))
+        .map(|(offset, mask)| RelocationMask {
+            offset: offset - start,
+            mask: *mask,
+        })
+        .collect()
+}
+
+// the digest of the bytes a fingerprint was calculated over, along with the
+// relocations used for it, if any
+fn digest_for_sig<W: Write>(
+    sig: &Fingerprint,
+    bytes: &[u8],
+    start: usize,
+    masks: Option<&BTreeMap<usize, u32>>,
+    options: &Options<W>,
+) -> (u64, Option<Vec<RelocationMask>>) {
+    let Fingerprint::V0(fp) = sig;
+    let size = fp.size() as usize;
+    let range = &bytes[start..(start + size)];
+
+    match masks {
+        Some(masks) => {
+            let relocations = relocations_for_range(masks, start, size);
+            let digest = digest_for_relocated_range(range, &relocations, options.mips_family);
+            (digest, Some(relocations))
+        }
+        None => (digest_for_range(range, options.mips_family), None),
+    }
+}
+
 /// classifies the RODATA of the object (if present) as being one of the following:
 ///
 ///    * only jump tables
+///    * starts and ends with jump tables
 ///    * starts with jump tables
 ///    * ends with jump tables
+///    * unknown, with no jump table at either end
 ///
 /// this strategy is then used to scan for matching RODATA segments in other
-/// files.
+/// files. unknown RODATA has nothing to anchor to, so it is found by its
+/// contents instead, with words which point into `pointers_to` masked.
 fn calculate_rodata_signature<W: Write>(
     map: &ObjectMap,
-    bytes: &[u8],
+    rodata: &[u8],
+    pointers_to: &Range<usize>,
     options: &Options<W>,
 ) -> Option<RODataSignature> {
-    let Some(ref rodata_info) = map.rodata else {
-        return None;
+    let rodata_info = map.rodata.as_ref()?;
+
+    // assumption: jump tables will be addresses inside of a text symbol, but cannot
+    // be the same value of any text symbol.
+    let words = rodata
+        .chunks_exact(4)
+        .map(|word| mips::read_word(word, options.mips_family))
+        .collect::<Vec<u32>>();
+    let entries = words
+        .iter()
+        .map(|word| map.is_address_inside_function(*word as usize))
+        .collect::<Vec<bool>>();
+
+    let starts_with_jump_table = *entries.first()?;
+    let ends_with_jump_table = *entries.last()?;
+
+    let rodata_type = if entries.iter().all(|entry| *entry) {
+        RODataSignatureType::OnlyJumpTables
+    } else if starts_with_jump_table && ends_with_jump_table {
+        RODataSignatureType::StartsAndEndsWithJumpTable
+    } else if starts_with_jump_table {
+        RODataSignatureType::StartsWithJumpTable
+    } else if ends_with_jump_table {
+        RODataSignatureType::EndsWithJumpTable
+    } else {
+        RODataSignatureType::Unknown
     };
 
-    return None;
-    /*
-
-        // assumption: jump tables will be addresses inside of a text symbol, but cannot
-        // be the same value of any text symbol.
-
-        let mut starts_with_jump_table = false;
-        let mut found_non_jump_table_entry = false;
-        let mut last_entry_was_jump_table = false;
-
-        let size = rodata_info.size;
-
-        let offset = rodata_info.vrom;
-        let last_offset = offset + size - 4;
-
-        for i in (offset..(offset + size)).step_by(4) {
-            let addr = mips::read_word(&bytes[i..(i + 4)], options.mips_family);
-
-            if map.is_address_inside_function(addr as usize) {
-                last_entry_was_jump_table = true;
-                if offset == 0 {
-                    starts_with_jump_table = true
+    let (fingerprint, anchor, digest, relocations) = match rodata_type {
+        RODataSignatureType::Unknown => {
+            // jump tables in the middle are relocated with the code, and
+            // pointers to functions, strings, or tables with the object
+            let relocations = words
+                .iter()
+                .zip(entries)
+                .enumerate()
+                .filter(|(_, (word, entry))| *entry || pointers_to.contains(&(**word as usize)))
+                .map(|(i, _)| RelocationMask {
+                    offset: i * 4,
+                    mask: 0xFFFFFFFF,
+                })
+                .collect::<Vec<_>>();
+            let digest = digest_for_relocated_range(
+                &rodata[..(words.len() * 4)],
+                &relocations,
+                options.mips_family,
+            );
+            let fingerprint = longest_unmasked_run(&words, &relocations).and_then(|anchor| {
+                let hasher =
+                    RabinKarpMIPSHasher::new_with_modulus(options.mips_family, options.modulus);
+                let (_, hash) = hasher
+                    .rolling_hashes(&words[anchor.clone()], anchor.len())
+                    .next()?;
+                let fingerprint = Fingerprint::V0(FingerprintV0::new_with_modulus(
+                    (anchor.len() * 4) as u64,
+                    hash,
+                    options.modulus,
+                ));
+                Some((fingerprint, anchor.start * 4))
+            });
+            match fingerprint {
+                Some((fingerprint, anchor)) => {
+                    (Some(fingerprint), anchor, Some(digest), relocations)
                 }
-            } else {
-                last_entry_was_jump_table = false;
-                found_non_jump_table_entry = true;
+                None => (None, 0, None, Vec::new()),
             }
         }
+        _ => (None, 0, None, Vec::new()),
+    };
 
-        if !found_non_jump_table_entry {
-            return Some(RODataSignature {
-                rodataType: RODataSignatureType::OnlyJumpTables,
-                size: size,
-            });
-        }
-        if starts_with_jump_table && last_entry_was_jump_table {
-            return Some(RODataSignature {
-                rodataType: RODataSignatureType::StartsAndEndsWithJumpTable,
-                size: size,
-            });
-        }
-        if starts_with_jump_table {
-            return Some(RODataSignature {
-                rodataType: RODataSignatureType::StartsWithJumpTable,
-                size: size,
-            });
-        }
-        if last_entry_was_jump_table {
-            return Some(RODataSignature {
-                rodataType: RODataSignatureType::EndsWithJumpTable,
-                size: size,
-            });
+    Some(RODataSignature {
+        rodata_type,
+        size: rodata_info.size,
+        fingerprint,
+        anchor,
+        digest,
+        relocations,
+    })
+}
+
+// the names of the functions at each address of a linked reference.
+// overlays sharing addresses may have more than one
+type FunctionNames = HashMap<usize, Vec<String>>;
+
+fn function_names<'a>(segments: impl Iterator<Item = &'a ObjectMap>) -> FunctionNames {
+    let mut names = FunctionNames::new();
+    for function in segments.flat_map(|map| map.text_symbols.iter()) {
+        let names = names.entry(function.vram).or_default();
+        if !names.contains(&function.name) {
+            names.push(function.name.clone());
         }
+    }
+    names
+}
+
+// the functions called by `code`, a function of `map` linked at `vram`. a
+// call is named after the function of `map` at its target, if there is
+// one, otherwise the target must have only one name
+fn call_sites(
+    code: &[u8],
+    vram: usize,
+    map: &ObjectMap,
+    names: &FunctionNames,
+    family: MIPSFamily,
+) -> Vec<CallSite> {
+    code.chunks_exact(4)
+        .enumerate()
+        .filter_map(|(i, word)| {
+            let instruction = mips::read_word(word, family);
+            if instruction >> 26 != mips::OP_JAL {
+                return None;
+            }
 
-        Some(RODataSignature {
-            rodataType: RODataSignatureType::Unknown,
-            size: size,
+            let offset = i * 4;
+            let target = ((vram + offset) & !mips::TARGET_MASK) | mips::jump_target(instruction);
+            let name = match map.text_symbols.iter().find(|f| f.vram == target) {
+                Some(function) => function.name.clone(),
+                None => match names.get(&target)?.as_slice() {
+                    [name] => name.clone(),
+                    _ => return None,
+                },
+            };
+            Some(CallSite { offset, name })
         })
-    */
+        .collect()
 }
 
-fn calculate_object_hashes<W: Write>(map: &ObjectMap, bytes: &[u8], options: &mut Options<W>) {
+// fingerprints an object as a segment. the code of objects which have not
+// been `linked` holds relocation addends rather than addresses, so their
+// references are not recorded. the calls of each function are named after
+// `names`, the functions of the linked reference, when there are any
+fn calculate_object_hashes<W: Write>(
+    map: &ObjectMap,
+    bytes: &[u8],
+    masks: Option<&BTreeMap<usize, u32>>,
+    rodata: Option<RODataSignature>,
+    linked: bool,
+    names: Option<&FunctionNames>,
+    options: &mut Options<W>,
+) {
     let start = map.offset - map.vrom;
     let end = start + map.size;
     let object_hash = sig_for_range(&bytes[start..end], options);
+    let (object_digest, object_relocations) =
+        digest_for_sig(&object_hash, bytes, start, masks, options);
 
     let mut functions = Vec::new();
 
@@ -425,22 +599,52 @@ fn calculate_object_hashes<W: Write>(map: &ObjectMap, bytes: &[u8], options: &mu
         let start = symbol.offset - map.vrom;
         let end = start + symbol.size;
         let segment_hash = sig_for_range(&bytes[start..end], options);
+        let (segment_digest, relocations) =
+            digest_for_sig(&segment_hash, bytes, start, masks, options);
+
+        let calls = names
+            .map(|names| {
+                call_sites(
+                    &bytes[start..end],
+                    symbol.vram,
+                    map,
+                    names,
+                    options.mips_family,
+                )
+            })
+            .unwrap_or_default();
 
         functions.push(FunctionSignature {
             name: symbol.name.clone(),
             fingerprint: segment_hash,
             size: symbol.size,
+            size_inferred: symbol.size_inferred,
+            digest: Some(segment_digest),
+            relocations,
+            calls,
         });
     }
 
-    let rodata_signature = calculate_rodata_signature(map, bytes, options);
+    let references = if linked {
+        delta::internal_references(
+            &bytes[start..end],
+            map.vram,
+            map.vram..(map.vram + map.size),
+            options.mips_family,
+        )
+    } else {
+        Vec::new()
+    };
 
     let sig = SegmentSignature {
-        name: map.name().to_string(),
+        name: map.name(),
         fingerprint: object_hash,
         size: map.size,
+        digest: Some(object_digest),
+        relocations: object_relocations,
         family: options.mips_family,
-        rodata: rodata_signature,
+        rodata,
+        references,
         functions,
     };
 
@@ -448,31 +652,401 @@ fn calculate_object_hashes<W: Write>(map: &ObjectMap, bytes: &[u8], options: &mu
     sig.serialize_to_yaml(&mut options.writer);
 }
 
+// the section containing the segment. overlays can share the same
+// addresses, so when more than one section does, the one loaded from the
+// segment's load address (or file offset) is used. when none of them is,
+// the segment cannot be told apart and `None` is returned
 fn data_for_segment<'a>(
-    data: &'a HashMap<usize, Vec<u8>>,
+    sections: &'a [elf::SectionData],
     segment: &ObjectMap,
-) -> Option<&'a Vec<u8>> {
-    for (addr, bin) in data {
-        if segment.vram >= *addr && segment.vram < (addr + bin.len()) {
-            return Some(bin);
-        }
+) -> Option<&'a elf::SectionData> {
+    let candidates = sections
+        .iter()
+        .filter(|section| section.contains_vram(segment.vram))
+        .collect::<Vec<_>>();
+    if candidates.len() < 2 {
+        return candidates.first().copied();
     }
 
-    None
+    candidates
+        .iter()
+        .find(|section| section.vrom == segment.vrom || section.offset == segment.vrom)
+        .or_else(|| {
+            candidates
+                .iter()
+                .find(|section| section.contains_vrom(segment.offset))
+        })
+        .copied()
+}
+
+// the RODATA of a segment, from the section it was linked into. like code,
+// overlays can share the addresses of their RODATA, which is only used when
+// the section it was loaded from is known
+fn rodata_for_segment<'a>(
+    sections: impl Iterator<Item = &'a elf::SectionData>,
+    segment: &ObjectMap,
+) -> Option<&'a [u8]> {
+    let rodata = segment.rodata.as_ref()?;
+    let candidates = sections
+        .filter(|section| section.contains_vram(rodata.vram))
+        .collect::<Vec<_>>();
+    let section = match candidates.as_slice() {
+        [section] => section,
+        _ => candidates
+            .iter()
+            .find(|section| section.contains_vrom(rodata.vrom))?,
+    };
+
+    let start = rodata.vram - section.vram;
+    section.data.get(start..(start + rodata.size))
 }
 
 pub fn fingerprint<W: Write>(map_file: &Path, elf_file: &Path, options: &mut Options<W>) {
-    let elf_symbols = elf::function_symbols(elf_file);
-    let segments = read_segments(map_file, ".text", elf_symbols);
-    let bin_data = elf::bin_data(elf_file);
+    fingerprint_map(map_file, MapFormat::Auto, elf_file, options);
+}
+
+/// Fingerprints an ELF using a map file written in `map_format`.
+pub fn fingerprint_map<W: Write>(
+    map_file: &Path,
+    map_format: MapFormat,
+    elf_file: &Path,
+    options: &mut Options<W>,
+) {
+    let elf_symbols = elf::function_symbols(elf_file, options.include_labels);
+    let segments = read_segments(map_file, map_format, ".text", elf_symbols);
+    fingerprint_segments(segments, elf_file, options);
+
+    if options.include_data {
+        let data_symbols = elf::data_symbols(elf_file);
+        let segments = read_segments(map_file, map_format, ".data", data_symbols);
+        fingerprint_data_segments(segments, elf_file, options);
+    }
+}
+
+/// Fingerprints an ELF without a map file. Functions are grouped into
+/// segments by the source file they were compiled from, see
+/// [`elf::file_segments`]. Data is only fingerprinted with a map, so
+/// `options.include_data` is ignored.
+pub fn fingerprint_elf<W: Write>(elf_file: &Path, options: &mut Options<W>) {
+    let segments = elf::file_segments(elf_file, options.include_labels);
+    fingerprint_segments(segments, elf_file, options);
+}
+
+fn fingerprint_segments<W: Write>(
+    segments: Vec<ObjectMap>,
+    elf_file: &Path,
+    options: &mut Options<W>,
+) {
+    let sections = elf::bin_data(elf_file);
+    let rodata_sections = elf::rodata_sections(elf_file);
+    let pointers_to = elf::vram_range(elf_file);
+
+    // an ELF linked with relocations (`--emit-relocs`) is masked exactly,
+    // otherwise instructions are masked by opcode
+    let relocation_masks = elf::relocation_masks(elf_file);
+    let no_masks = BTreeMap::new();
+
+    if let Some(family) = elf::mips_family(elf_file) {
+        options.mips_family = family;
+    }
+
+    let names = function_names(segments.iter());
+    for map in segments {
+        if let Some(section) = data_for_segment(&sections, &map) {
+            let masks = if relocation_masks.is_empty() {
+                None
+            } else {
+                Some(relocation_masks.get(&section.index).unwrap_or(&no_masks))
+            };
+            // RODATA is often linked into the code's section, but may have
+            // its own
+            let rodata = rodata_for_segment(sections.iter().chain(rodata_sections.iter()), &map)
+                .and_then(|rodata| calculate_rodata_signature(&map, rodata, &pointers_to, options));
+            calculate_object_hashes(
+                &map,
+                &section.data,
+                masks,
+                rodata,
+                true,
+                Some(&names),
+                options,
+            );
+        }
+    }
+}
+
+// the longest run of `words` which are not masked by `relocations`, without
+// leading or trailing zero words. `None` if every word is masked or zero
+fn longest_unmasked_run(words: &[u32], relocations: &[RelocationMask]) -> Option<Range<usize>> {
+    let mut masked = vec![false; words.len()];
+    for relocation in relocations {
+        masked[relocation.offset / 4] = true;
+    }
+
+    let mut best: Option<Range<usize>> = None;
+    let mut start = 0;
+    // a masked word past the end closes the last run
+    for (end, _) in masked
+        .iter()
+        .chain(std::iter::once(&true))
+        .enumerate()
+        .filter(|(_, masked)| **masked)
+    {
+        let mut run = start..end;
+        while run.start < run.end && words[run.start] == 0 {
+            run.start += 1;
+        }
+        while run.end > run.start && words[run.end - 1] == 0 {
+            run.end -= 1;
+        }
+        if !run.is_empty() && best.as_ref().is_none_or(|best| run.len() > best.len()) {
+            best = Some(run);
+        }
+        start = end + 1;
+    }
+
+    best
+}
+
+// the signature of an object's data. words which point into `pointers_to`
+// or which are relocated are masked. `None` if there is nothing left to
+// find the data by
+fn calculate_data_signature<W: Write>(
+    map: &ObjectMap,
+    section: &str,
+    bytes: &[u8],
+    masks: Option<&BTreeMap<usize, u32>>,
+    pointers_to: &Range<usize>,
+    options: &Options<W>,
+) -> Option<DataSignature> {
+    let start = map.offset - map.vrom;
+    let size = map.size & !3;
+    let data = bytes.get(start..(start + size))?;
+
+    let words = data
+        .chunks(4)
+        .map(|word| mips::read_word(word, options.mips_family))
+        .collect::<Vec<u32>>();
+
+    let mut relocations = Vec::new();
+    for (i, word) in words.iter().enumerate() {
+        let mut mask = masks
+            .and_then(|masks| masks.get(&(start + i * 4)))
+            .copied()
+            .unwrap_or(0);
+        if pointers_to.contains(&(*word as usize)) {
+            mask = 0xFFFFFFFF;
+        }
+        if mask != 0 {
+            relocations.push(RelocationMask {
+                offset: i * 4,
+                mask,
+            });
+        }
+    }
+
+    let anchor = longest_unmasked_run(&words, &relocations)?;
+    let hasher = RabinKarpMIPSHasher::new_with_modulus(options.mips_family, options.modulus);
+    let (_, hash) = hasher
+        .rolling_hashes(&words[anchor.clone()], anchor.len())
+        .next()?;
+
+    let symbols = map
+        .text_symbols
+        .iter()
+        .map(|symbol| (symbol.name.clone(), symbol.offset - map.offset))
+        .collect::<HashMap<_, _>>();
+
+    Some(DataSignature {
+        name: map.name(),
+        section: section.to_string(),
+        fingerprint: Fingerprint::V0(FingerprintV0::new_with_modulus(
+            (anchor.len() * 4) as u64,
+            hash,
+            options.modulus,
+        )),
+        anchor: anchor.start * 4,
+        size,
+        digest: digest_for_relocated_range(data, &relocations, options.mips_family),
+        relocations,
+        family: options.mips_family,
+        symbols,
+    })
+}
+
+// fingerprints the `.data` of each object in `segments`. the symbols of
+// each segment are its data symbols
+fn fingerprint_data_segments<W: Write>(
+    segments: Vec<ObjectMap>,
+    elf_file: &Path,
+    options: &mut Options<W>,
+) {
+    let sections = elf::data_sections(elf_file);
+    let relocation_masks = elf::relocation_masks(elf_file);
+    let pointers_to = elf::vram_range(elf_file);
 
     if let Some(family) = elf::mips_family(elf_file) {
         options.mips_family = family;
     }
 
     for map in segments {
-        if let Some(data) = data_for_segment(&bin_data, &map) {
-            calculate_object_hashes(&map, data, options);
+        let Some(section) = data_for_segment(&sections, &map) else {
+            continue;
+        };
+        let Some(signature) = calculate_data_signature(
+            &map,
+            ".data",
+            &section.data,
+            relocation_masks.get(&section.index),
+            &pointers_to,
+            options,
+        ) else {
+            continue;
+        };
+
+        writeln!(options.writer, "---").expect("Write ocument separator");
+        signature.serialize_to_yaml(&mut options.writer);
+    }
+}
+
+// the addresses spanned by the code and RODATA of the segments of a splat
+// config, which any pointer into the binary falls within
+fn splat_vram_range(segments: &[ObjectMap]) -> Range<usize> {
+    segments
+        .iter()
+        .flat_map(|map| {
+            let rodata = map
+                .rodata
+                .as_ref()
+                .map(|rodata| rodata.vram..(rodata.vram + rodata.size));
+            std::iter::once(map.vram..(map.vram + map.size)).chain(rodata)
+        })
+        .reduce(|a, b| cmp::min(a.start, b.start)..cmp::max(a.end, b.end))
+        .unwrap_or(0..0)
+}
+
+/// Fingerprints the code subsegments of a splat config in the original
+/// binary it was split from. Function names and sizes come from
+/// `symbol_addrs`, see [`splat::read_segments`]. Returns an error when the
+/// config is not valid YAML.
+pub fn fingerprint_splat<W: Write>(
+    config_file: &Path,
+    symbol_addrs_file: &Path,
+    bin_file: &Path,
+    options: &mut Options<W>,
+) -> Result<(), serde_yaml::Error> {
+    let splat = splat::read_splat(config_file, symbol_addrs_file)?;
+    let file_data = MappedFile::open(bin_file).expect("Could not read file.");
+    let bytes = file_data.as_slice();
+
+    if let Some(family) = splat.family {
+        options.mips_family = family;
+    }
+
+    let names = function_names(splat.segments.iter());
+    let pointers_to = splat_vram_range(&splat.segments);
+    for map in splat.segments {
+        if map.size == 0 || map.offset + map.size > bytes.len() {
+            continue;
+        }
+        let rodata = map
+            .rodata
+            .as_ref()
+            .and_then(|rodata| bytes.get(rodata.vrom..(rodata.vrom + rodata.size)))
+            .and_then(|rodata| calculate_rodata_signature(&map, rodata, &pointers_to, options));
+        calculate_object_hashes(&map, bytes, None, rodata, true, Some(&names), options);
+    }
+    Ok(())
+}
+
+// the static libraries and relocatable objects in `paths`. directories
+// are searched for `.a` and `.o` files
+fn library_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+
+        let mut entries = fs::read_dir(path)
+            .expect("library directory")
+            .map(|entry| entry.expect("library directory entry").path())
+            .collect::<Vec<_>>();
+        entries.sort();
+
+        for entry in entries {
+            let is_library = entry
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| ext.eq_ignore_ascii_case("a") || ext.eq_ignore_ascii_case("o"))
+                .unwrap_or(false);
+            if entry.is_dir() {
+                files.extend(library_files(&[entry]));
+            } else if is_library {
+                files.push(entry);
+            }
+        }
+    }
+    files
+}
+
+// fingerprints the code of a single relocatable object as a segment
+fn calculate_relocatable_object_hashes<W: Write>(
+    name: &str,
+    data: &[u8],
+    options: &mut Options<W>,
+) {
+    let Some(code) = elf::object_code(data, options.include_labels) else {
+        return;
+    };
+    if code.bytes.is_empty() {
+        return;
+    }
+
+    if let Some(family) = code.family {
+        options.mips_family = family;
+    }
+
+    let map = ObjectMap {
+        object: name.to_string(),
+        offset: 0,
+        vram: 0,
+        vrom: 0,
+        size: code.bytes.len(),
+        rodata: None,
+        text_symbols: code.functions,
+    };
+    calculate_object_hashes(
+        &map,
+        &code.bytes,
+        Some(&code.relocation_masks),
+        None,
+        false,
+        None,
+        options,
+    );
+}
+
+/// Fingerprints static libraries (`.a`) and relocatable objects (`.o`)
+/// without linking them. Each object, or archive member, is a segment and
+/// each of its function symbols is a function. Signatures are masked
+/// exactly by the object's relocations.
+pub fn fingerprint_lib<W: Write>(paths: &[PathBuf], options: &mut Options<W>) {
+    for path in library_files(paths) {
+        let file_data = MappedFile::open(&path).expect("Could not read file.");
+        let bytes = file_data.as_slice();
+
+        if archive::is_archive(bytes) {
+            let members = archive::members(bytes).expect("Parse archive");
+            for member in members {
+                // named the way a map file names archive members
+                let name = format!("{}({})", path.display(), member.name);
+                calculate_relocatable_object_hashes(&name, member.data, options);
+            }
+        } else {
+            let name = path.to_str().expect("object path");
+            calculate_relocatable_object_hashes(name, bytes, options);
         }
     }
 }
@@ -480,6 +1054,7 @@ pub fn fingerprint<W: Write>(map_file: &Path, elf_file: &Path, options: &mut Opt
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::map::{FunctionEntry, SegmentInfo};
     use std::io::Cursor;
 
     #[test]
@@ -551,4 +1126,176 @@ mod tests {
         assert_eq!(f2.size(), 8);
         assert_eq!(f2.hash(), 0x41E00088);
     }
+
+    #[test]
+    fn test_digest_for_range() {
+        let family = MIPSFamily::R3000GTE;
+        let jr_ra_nop: [u8; 8] = [0x08, 0x00, 0xE0, 0x03, 0, 0, 0, 0];
+        let lui_nop: [u8; 8] = [0x80, 0x01, 0x02, 0x3C, 0, 0, 0, 0];
+
+        // the digest of nothing is the FNV offset basis
+        assert_eq!(digest_for_range(&[], family), 0xCBF29CE484222325);
+
+        assert_ne!(
+            digest_for_range(&jr_ra_nop, family),
+            digest_for_range(&lui_nop, family)
+        );
+
+        // masked fields do not contribute to the digest
+        let lui_other: [u8; 8] = [0x11, 0x80, 0x02, 0x3C, 0, 0, 0, 0];
+        assert_eq!(
+            digest_for_range(&lui_nop, family),
+            digest_for_range(&lui_other, family)
+        );
+
+        // with relocations, only the relocated fields are masked
+        let relocations = [RelocationMask {
+            offset: 0,
+            mask: 0x0000FFFF,
+        }];
+        assert_eq!(
+            digest_for_relocated_range(&lui_nop, &relocations, family),
+            digest_for_relocated_range(&lui_other, &relocations, family)
+        );
+        assert_ne!(
+            digest_for_relocated_range(&lui_nop, &[], family),
+            digest_for_relocated_range(&lui_other, &[], family)
+        );
+    }
+
+    #[test]
+    fn test_data_for_segment() {
+        // two overlays at the same address, loaded from different places
+        let section = |index, vrom, offset| elf::SectionData {
+            index,
+            vram: 0x80100000,
+            vrom,
+            offset,
+            data: vec![0; 0x100],
+        };
+        let sections = [section(1, 0x1000, 0x400), section(2, 0x2000, 0x500)];
+        let segment = |vrom, offset| ObjectMap {
+            object: "test.o".to_string(),
+            offset,
+            vram: 0x80100010,
+            vrom,
+            size: 0x10,
+            rodata: Some(SegmentInfo {
+                vram: 0x80100080,
+                vrom: vrom + 0x70,
+                size: 0x10,
+            }),
+            text_symbols: Vec::new(),
+        };
+        let index =
+            |segment: &ObjectMap| data_for_segment(&sections, segment).map(|section| section.index);
+        let rodata = |segment: &ObjectMap| rodata_for_segment(sections.iter(), segment).is_some();
+
+        assert_eq!(index(&segment(0x2000, 0x2010)), Some(2));
+        assert_eq!(index(&segment(0x500, 0x3000)), Some(2));
+        assert_eq!(index(&segment(0x3000, 0x1010)), Some(1));
+        assert!(rodata(&segment(0x1010, 0x1010)));
+
+        // neither overlay was loaded from where the segment was
+        assert_eq!(index(&segment(0x3000, 0x3000)), None);
+        assert!(!rodata(&segment(0x3000, 0x3000)));
+
+        // an address only one section has is not ambiguous
+        let segment = segment(0x3000, 0x3000);
+        assert!(data_for_segment(&sections[..1], &segment).is_some());
+        assert!(rodata_for_segment(sections[..1].iter(), &segment).is_some());
+    }
+
+    #[test]
+    fn test_calculate_rodata_signature() {
+        let options = Options::new(Cursor::new(Vec::new()));
+        let map = ObjectMap {
+            object: "test.o".to_string(),
+            offset: 0,
+            vram: 0x80010000,
+            vrom: 0,
+            size: 0x20,
+            rodata: Some(SegmentInfo {
+                vram: 0x80020000,
+                vrom: 0x10000,
+                size: 0x10,
+            }),
+            text_symbols: vec![FunctionEntry {
+                name: "func".to_string(),
+                offset: 0,
+                vram: 0x80010000,
+                size: 0x20,
+                size_inferred: false,
+            }],
+        };
+
+        // a jump table entry, a function pointer, a pointer to the
+        // object's RODATA, and a constant
+        let entry = 0x80010008u32.to_le_bytes();
+        let pointer = 0x80010000u32.to_le_bytes();
+        let string = 0x80020008u32.to_le_bytes();
+        let constant = 42u32.to_le_bytes();
+        let pointers_to = 0x80010000..0x80020010;
+        let signature = |words: &[[u8; 4]]| {
+            calculate_rodata_signature(&map, &words.concat(), &pointers_to, &options)
+        };
+        let rodata_type = |words: &[[u8; 4]]| signature(words).map(|rodata| rodata.rodata_type);
+
+        assert_eq!(
+            rodata_type(&[entry, entry]),
+            Some(RODataSignatureType::OnlyJumpTables)
+        );
+        assert_eq!(
+            rodata_type(&[entry, constant, entry]),
+            Some(RODataSignatureType::StartsAndEndsWithJumpTable)
+        );
+        assert_eq!(
+            rodata_type(&[entry, constant]),
+            Some(RODataSignatureType::StartsWithJumpTable)
+        );
+        assert_eq!(
+            rodata_type(&[pointer, entry]),
+            Some(RODataSignatureType::EndsWithJumpTable)
+        );
+        assert_eq!(
+            rodata_type(&[constant, entry, pointer]),
+            Some(RODataSignatureType::Unknown)
+        );
+        assert_eq!(rodata_type(&[]), None);
+
+        // only unknown RODATA is found by its contents, which do not depend
+        // on where the code was linked
+        assert!(signature(&[constant, entry]).unwrap().fingerprint.is_none());
+        let unknown = signature(&[constant, entry, pointer, string, constant]).unwrap();
+        assert!(unknown.fingerprint.is_some());
+        assert_eq!(unknown.anchor, 0);
+        assert_eq!(
+            unknown.relocations,
+            [4, 8, 12]
+                .into_iter()
+                .map(|offset| RelocationMask {
+                    offset,
+                    mask: 0xFFFFFFFF,
+                })
+                .collect::<Vec<_>>()
+        );
+
+        // the jump table entry, the function pointer, and the pointer to
+        // the string all moved
+        let moved = signature(&[
+            constant,
+            0x80010010u32.to_le_bytes(),
+            0x80011000u32.to_le_bytes(),
+            0x80020004u32.to_le_bytes(),
+            constant,
+        ])
+        .unwrap();
+        assert_eq!(unknown.fingerprint, moved.fingerprint);
+        assert_eq!(unknown.digest, moved.digest);
+        assert_eq!(unknown.relocations, moved.relocations);
+
+        // a constant which changed is not masked
+        let changed = signature(&[43u32.to_le_bytes(), entry, pointer, string, constant]).unwrap();
+        assert_ne!(unknown.digest, changed.digest);
+    }
 }
@@ -0,0 +1,156 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+use std::thread;
+
+use crate::arch::mips;
+use crate::fingerprint::{digest_for_relocated_range, digest_for_words};
+use crate::{MIPSFamily, RelocationMask};
+
+// images with fewer words than this are normalized on the calling thread
+const PARALLEL_THRESHOLD: usize = 0x10000;
+
+/// A bin decoded into words and normalized once so that any number of
+/// searches can share it.
+///
+/// Offsets into the image are byte offsets into the original bin, which is
+/// kept for checks that need the instructions before normalization. Any
+/// trailing bytes which don't make up a full word are dropped.
+#[derive(Debug, Clone)]
+pub struct NormalizedImage<'a> {
+    family: MIPSFamily,
+    bytes: &'a [u8],
+    words: Vec<u32>,
+}
+
+impl<'a> NormalizedImage<'a> {
+    /// Decodes and normalizes every word of `bytes`. Large images are
+    /// split into chunks which are normalized in parallel.
+    pub fn new(bytes: &'a [u8], family: MIPSFamily) -> Self {
+        let len = bytes.len() / 4;
+        let bytes = &bytes[..len * 4];
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+
+        let mut words = vec![0; len];
+        if len < PARALLEL_THRESHOLD || threads == 1 {
+            normalize_into(&mut words, bytes, family);
+        } else {
+            let chunk_words = len.div_ceil(threads);
+            thread::scope(|scope| {
+                for (words, bytes) in words
+                    .chunks_mut(chunk_words)
+                    .zip(bytes.chunks(chunk_words * 4))
+                {
+                    scope.spawn(move || normalize_into(words, bytes, family));
+                }
+            });
+        }
+
+        Self {
+            family,
+            bytes,
+            words,
+        }
+    }
+
+    pub fn family(&self) -> MIPSFamily {
+        self.family
+    }
+
+    /// The bytes of the image before normalization.
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Every normalized word of the image.
+    pub fn words(&self) -> &[u32] {
+        &self.words
+    }
+
+    /// The normalized words in the bytes `start..end`, clamped to the
+    /// image. Both offsets are rounded down to a word boundary.
+    pub fn window(&self, start: usize, end: usize) -> &[u32] {
+        let end = end.min(self.len()) / 4;
+        let start = start.min(end * 4) / 4;
+        &self.words[start..end]
+    }
+
+    /// The size of the image in bytes.
+    pub fn len(&self) -> usize {
+        self.words.len() * 4
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// The digest of the `size` bytes at `offset`, or `None` if they extend
+    /// past the end of the image. See
+    /// [`digest_for_range`](crate::fingerprint::digest_for_range).
+    pub fn digest(&self, offset: usize, size: usize) -> Option<u64> {
+        let end = offset.checked_add(size)?;
+        if end > self.len() {
+            return None;
+        }
+        Some(digest_for_words(&self.words[offset / 4..end / 4]))
+    }
+
+    /// The digest of the `size` bytes at `offset` with only the fields in
+    /// `relocations` masked, or `None` if they extend past the end of the
+    /// image. See
+    /// [`digest_for_relocated_range`](crate::fingerprint::digest_for_relocated_range).
+    pub fn relocated_digest(
+        &self,
+        offset: usize,
+        size: usize,
+        relocations: &[RelocationMask],
+    ) -> Option<u64> {
+        let end = offset.checked_add(size)?;
+        if end > self.len() {
+            return None;
+        }
+        Some(digest_for_relocated_range(
+            &self.bytes[offset..end],
+            relocations,
+            self.family,
+        ))
+    }
+}
+
+fn normalize_into(words: &mut [u32], bytes: &[u8], family: MIPSFamily) {
+    for (word, ins) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = mips::bytes_to_normalized_instruction(ins, family);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fingerprint::digest_for_range;
+
+    #[test]
+    fn test_normalized_image() {
+        // large enough to be normalized in parallel, with a trailing byte
+        let bytes = (0..(PARALLEL_THRESHOLD * 4 + 0x101))
+            .map(|i| (i * 7) as u8)
+            .collect::<Vec<u8>>();
+        let image = NormalizedImage::new(&bytes, MIPSFamily::R3000GTE);
+
+        assert_eq!(image.len(), PARALLEL_THRESHOLD * 4 + 0x100);
+        let expected = bytes
+            .chunks_exact(4)
+            .map(|ins| mips::bytes_to_normalized_instruction(ins, MIPSFamily::R3000GTE))
+            .collect::<Vec<u32>>();
+        assert_eq!(image.words(), expected);
+
+        assert_eq!(image.window(8, 16), &expected[2..4]);
+        assert_eq!(
+            image.window(image.len() - 4, usize::MAX),
+            &expected[expected.len() - 1..]
+        );
+        assert_eq!(
+            image.digest(0x40, 0x20),
+            Some(digest_for_range(&bytes[0x40..0x60], MIPSFamily::R3000GTE))
+        );
+        assert_eq!(image.digest(image.len() - 4, 8), None);
+    }
+}
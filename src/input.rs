@@ -0,0 +1,69 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::ops::Deref;
+use std::path::Path;
+
+/// The contents of a file, memory-mapped rather than read.
+///
+/// Pages are only loaded as they are touched and can be dropped by the OS
+/// under memory pressure, so files larger than RAM can be searched.
+///
+/// The file must not be truncated or modified while it is open. Touching a
+/// page past the end of a truncated file kills the process with `SIGBUS`,
+/// and any other change is visible through the slice this derefs to, which
+/// Rust assumes cannot change. Pipes, devices and other files which are not
+/// regular files are read into memory instead.
+#[derive(Debug)]
+pub struct MappedFile {
+    contents: Contents,
+}
+
+#[derive(Debug)]
+enum Contents {
+    Mapped(Mmap),
+    // empty files can't be mapped on every platform
+    Read(Vec<u8>),
+}
+
+impl MappedFile {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let metadata = file.metadata()?;
+        if !metadata.is_file() || metadata.len() == 0 {
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+            return Ok(Self {
+                contents: Contents::Read(bytes),
+            });
+        }
+
+        // SAFETY: the mapping is read-only, but other processes can still
+        // write to or truncate the file while it is mapped. Either breaks
+        // the assumption that the returned slice never changes, and a
+        // truncation raises `SIGBUS` when the lost pages are touched. This
+        // is only sound while nothing else modifies the file, as documented
+        // on `MappedFile`.
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Self {
+            contents: Contents::Mapped(map),
+        })
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        match &self.contents {
+            Contents::Mapped(map) => map,
+            Contents::Read(bytes) => bytes,
+        }
+    }
+}
+
+impl Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
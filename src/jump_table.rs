@@ -0,0 +1,120 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+use crate::arch::mips::{self, Registers, Value, FUNCT_JR, OP_SLTIU, OP_SPECIAL};
+use crate::MIPSFamily;
+
+/// A jump table a function dispatches through.
+#[derive(Debug, PartialEq)]
+pub struct JumpTable {
+    /// The address of the table.
+    pub vram: usize,
+    /// The number of entries, from the bounds check which guards the
+    /// dispatch, if there is one.
+    pub entries: Option<usize>,
+}
+
+/// Finds the jump tables `code` dispatches through by following the
+/// registers which compute their addresses:
+///
+/// ```pre
+/// sltiu $v0, $a0, 9
+/// beqz  $v0, .default
+/// sll   $v0, $a0, 2
+/// lui   $at, %hi(jtbl)
+/// addu  $at, $at, $v0
+/// lw    $v0, %lo(jtbl)($at)
+/// nop
+/// jr    $v0
+/// ```
+///
+/// The table address may also be completed with `addiu` before the index is
+/// added. Code is followed in order without regard for branches, which is
+/// enough for the sequences compilers emit.
+pub fn find_jump_tables(code: &[u8], family: MIPSFamily) -> Vec<JumpTable> {
+    let mut registers = Registers::new();
+    let mut bound = None;
+    let mut tables = Vec::new();
+
+    for word in code.chunks_exact(4) {
+        let instruction = mips::read_word(word, family);
+        let rs = ((instruction >> 21) & 0x1F) as usize;
+
+        match (instruction >> 26, instruction & 0x3F) {
+            (OP_SLTIU, _) => bound = Some(instruction as i16 as i32 as u32 as usize),
+            (OP_SPECIAL, FUNCT_JR) => {
+                if let Value::Loaded(address) = registers.get(rs) {
+                    tables.push(JumpTable {
+                        vram: address as usize,
+                        entries: bound.take(),
+                    });
+                }
+            }
+            _ => {}
+        }
+        registers.step(instruction);
+    }
+
+    tables
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arch::mips::assemble;
+
+    #[test]
+    fn test_find_jump_tables() {
+        let family = MIPSFamily::R3000GTE;
+
+        // the dispatch of hello_world in tt_004
+        let code = assemble(&[
+            0x2C820009, // sltiu $v0, $a0, 9
+            0x10400014, // beqz  $v0, .default
+            0xAFBF0010, // sw    $ra, 16($sp)
+            0x00041080, // sll   $v0, $a0, 2
+            0x3C018017, // lui   $at, 0x8017
+            0x00220821, // addu  $at, $at, $v0
+            0x8C220964, // lw    $v0, 0x964($at)
+            0x00000000, // nop
+            0x00400008, // jr    $v0
+            0x00000000, // nop
+        ]);
+        assert_eq!(
+            find_jump_tables(&code, family),
+            vec![JumpTable {
+                vram: 0x80170964,
+                entries: Some(9),
+            }]
+        );
+
+        // the table address completed before the index is added, without a
+        // bounds check, and a negative offset
+        let code = assemble(&[
+            0x3C038017, // lui   $v1, 0x8017
+            0x24638000, // addiu $v1, $v1, -0x8000
+            0x00431021, // addu  $v0, $v0, $v1
+            0x8C42FFFC, // lw    $v0, -4($v0)
+            0x00000000, // nop
+            0x00400008, // jr    $v0
+        ]);
+        assert_eq!(
+            find_jump_tables(&code, family),
+            vec![JumpTable {
+                vram: 0x80167FFC,
+                entries: None,
+            }]
+        );
+
+        // the loaded register is overwritten before the jump, and returns
+        // are not dispatches
+        let code = assemble(&[
+            0x3C018017, // lui   $at, 0x8017
+            0x00220821, // addu  $at, $at, $v0
+            0x8C220964, // lw    $v0, 0x964($at)
+            0x34020001, // ori   $v0, $zero, 1
+            0x00400008, // jr    $v0
+            0x03E00008, // jr    $ra
+        ]);
+        assert!(find_jump_tables(&code, family).is_empty());
+    }
+}
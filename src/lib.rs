@@ -1,5 +1,6 @@
 // SPDX-FileCopyrightText: © 2025 TTKB, LLC
 // SPDX-License-Identifier: BSD-3-CLAUSE
+use crate::delta::DeltaCheck;
 use crate::fingerprint::Fingerprint;
 use serde::{Deserialize, Serialize};
 use serde_with::{self, serde_as};
@@ -7,13 +8,23 @@ use std::collections::HashMap;
 use std::hash::Hash;
 use std::io::Write;
 
+pub mod address_space;
 pub mod arch;
+pub mod archive;
 pub mod cli;
+pub mod delta;
+pub mod dwarf;
 pub mod elf;
 pub mod fingerprint;
+pub mod image;
+pub mod input;
+pub mod jump_table;
 pub mod map;
+pub mod mdebug;
 pub mod rk;
 pub mod scan;
+pub mod splat;
+pub mod vram;
 
 /*
 #[derive(Parser, Debug)]
@@ -46,6 +57,11 @@ pub struct Options<W: Write> {
     pub radix: u64,
     pub writer: W,
     pub mips_family: MIPSFamily,
+    /// Treat global labels without a type in code as functions.
+    pub include_labels: bool,
+    /// Fingerprint the `.data` of objects along with their code. Only
+    /// used with a map file.
+    pub include_data: bool,
 }
 
 impl<W: Write> Options<W> {
@@ -56,6 +72,8 @@ impl<W: Write> Options<W> {
             radix: 4294967296,
             writer,
             mips_family: MIPSFamily::R3000GTE,
+            include_labels: false,
+            include_data: false,
         }
     }
 }
@@ -78,8 +96,76 @@ pub struct FunctionSignature {
     // #[serde_as(as = "serde_with::hex::Hex<serde_with::formats::Uppercase>")]
     pub fingerprint: Fingerprint,
     pub size: usize,
+    /// The function's symbol had no size, so `size` was inferred from the
+    /// symbol after it and may include alignment padding.
+    #[serde(default)]
+    pub size_inferred: bool,
+    /// An independent digest of the normalized instructions covered by
+    /// `fingerprint`, used to reject Rabin-Karp hash collisions.
+    #[serde(default)]
+    pub digest: Option<u64>,
+    /// The fields of the instructions covered by `fingerprint` which are
+    /// relocated, when the reference was built with relocations. When
+    /// present, `digest` covers every other bit of the instructions
+    /// exactly rather than masking by opcode.
+    #[serde(default)]
+    pub relocations: Option<Vec<RelocationMask>>,
+    /// The functions this function calls, by the offset of each `jal`.
+    #[serde(default)]
+    pub calls: Vec<CallSite>,
 }
 
+/// A `jal` at `offset` in a function, and the name of the function it
+/// called in the reference.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct CallSite {
+    pub offset: usize,
+    pub name: String,
+}
+
+/// The bits of the instruction at `offset` which are filled in by a
+/// relocation, and so differ between links.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct RelocationMask {
+    pub offset: usize,
+    pub mask: u32,
+}
+
+// writes the list of relocation masks, if there is one
+fn serialize_relocations<W: Write>(
+    indent: &str,
+    relocations: Option<&[RelocationMask]>,
+    writer: &mut W,
+) {
+    let Some(relocations) = relocations else {
+        return;
+    };
+
+    if relocations.is_empty() {
+        writeln!(writer, "{}relocations: []", indent).expect("relocations serialization");
+        return;
+    }
+
+    writeln!(writer, "{}relocations:", indent).expect("relocations key serialization");
+    for relocation in relocations {
+        writeln!(
+            writer,
+            "{}- {{ offset: 0x{:X}, mask: 0x{:08X} }}",
+            indent, relocation.offset, relocation.mask
+        )
+        .expect("relocation serialization");
+    }
+}
+
+/// An address within a segment which its own code refers to, and the
+/// offset of the instruction which completes the reference.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct AddressReference {
+    pub offset: usize,
+    pub address: usize,
+}
+
+/// How the RODATA of an object is found, by where its jump tables are.
 #[serde_as]
 #[derive(Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum RODataSignatureType {
@@ -93,8 +179,23 @@ pub enum RODataSignatureType {
 #[serde_as]
 #[derive(Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct RODataSignature {
-    rodataType: RODataSignatureType,
-    size: usize,
+    #[serde(rename = "rodataType")]
+    pub rodata_type: RODataSignatureType,
+    pub size: usize,
+    /// `Unknown` RODATA only: the fingerprint of the words at `anchor`,
+    /// the longest run of words without any pointers.
+    #[serde(default)]
+    pub fingerprint: Option<Fingerprint>,
+    #[serde(default)]
+    pub anchor: usize,
+    /// `Unknown` RODATA only: the digest of every word, with pointers
+    /// masked.
+    #[serde(default)]
+    pub digest: Option<u64>,
+    /// `Unknown` RODATA only: the masked bits of each word which holds a
+    /// pointer into the reference, including jump table entries.
+    #[serde(default)]
+    pub relocations: Vec<RelocationMask>,
 }
 
 #[serde_as]
@@ -104,8 +205,16 @@ pub struct SegmentSignature {
     // #[serde_as(as = "serde_with::hex::Hex<serde_with::formats::Uppercase>")]
     pub fingerprint: Fingerprint,
     pub size: usize,
+    #[serde(default)]
+    pub digest: Option<u64>,
+    #[serde(default)]
+    pub relocations: Option<Vec<RelocationMask>>,
     pub family: MIPSFamily,
     pub rodata: Option<RODataSignature>,
+    /// The calls, jumps, and addresses within the segment which its code
+    /// refers to, used to check that a match moved as a whole.
+    #[serde(default)]
+    pub references: Vec<AddressReference>,
     pub functions: Vec<FunctionSignature>,
 }
 
@@ -127,6 +236,11 @@ impl SerializeToYAML for SegmentSignature {
         )
         .expect("segment fingerprint serialization");
         writeln!(writer, "{}size: 0x{:X}", indent, self.size).expect("segment size serialization");
+        if let Some(digest) = self.digest {
+            writeln!(writer, "{}digest: 0x{:016X}", indent, digest)
+                .expect("segment digest serialization");
+        }
+        serialize_relocations(&indent, self.relocations.as_deref(), writer);
         writeln!(
             writer,
             "{}family: {}",
@@ -140,11 +254,41 @@ impl SerializeToYAML for SegmentSignature {
                 writer,
                 "{}  rodataType: {}",
                 indent,
-                serde_yaml::to_string(&rodata.rodataType).unwrap().trim()
+                serde_yaml::to_string(&rodata.rodata_type).unwrap().trim()
             )
             .expect("segment rodataType serialization");
             writeln!(writer, "{}  size: 0x{:X}", indent, rodata.size)
                 .expect("segment rodata.size serialization");
+            if let Some(ref fingerprint) = rodata.fingerprint {
+                writeln!(
+                    writer,
+                    "{}  fingerprint: {}",
+                    indent,
+                    serde_yaml::to_string(fingerprint).unwrap().trim()
+                )
+                .expect("segment rodata.fingerprint serialization");
+                writeln!(writer, "{}  anchor: 0x{:X}", indent, rodata.anchor)
+                    .expect("segment rodata.anchor serialization");
+            }
+            if let Some(digest) = rodata.digest {
+                writeln!(writer, "{}  digest: 0x{:016X}", indent, digest)
+                    .expect("segment rodata.digest serialization");
+            }
+            if !rodata.relocations.is_empty() {
+                serialize_relocations(&format!("{}  ", indent), Some(&rodata.relocations), writer);
+            }
+        }
+        if !self.references.is_empty() {
+            writeln!(writer, "{}references:", indent)
+                .expect("segment references key serialization");
+        }
+        for reference in self.references.iter() {
+            writeln!(
+                writer,
+                "{}- {{ offset: 0x{:X}, address: 0x{:08X} }}",
+                indent, reference.offset, reference.address
+            )
+            .expect("segment reference serialization");
         }
         writeln!(writer, "{}functions:", indent).expect("segment functions key serialization");
 
@@ -165,15 +309,153 @@ impl SerializeToYAML for SegmentSignature {
             .expect("function fingerprint serialization");
             writeln!(writer, "{}  size: 0x{:X}", indent, function.size)
                 .expect("function size serialization");
+            if function.size_inferred {
+                writeln!(writer, "{}  size_inferred: true", indent)
+                    .expect("function size_inferred serialization");
+            }
+            if let Some(digest) = function.digest {
+                writeln!(writer, "{}  digest: 0x{:016X}", indent, digest)
+                    .expect("function digest serialization");
+            }
+            serialize_relocations(
+                &format!("{}  ", indent),
+                function.relocations.as_deref(),
+                writer,
+            );
+            if !function.calls.is_empty() {
+                writeln!(writer, "{}  calls:", indent).expect("function calls key serialization");
+            }
+            for call in function.calls.iter() {
+                writeln!(
+                    writer,
+                    "{}  - {{ offset: 0x{:X}, name: {} }}",
+                    indent,
+                    call.offset,
+                    serde_yaml::to_string(&call.name).unwrap().trim()
+                )
+                .expect("function call serialization");
+            }
         }
     }
 }
 
+/// A fingerprint of the initialized data of an object, such as the entity
+/// and animation tables shared between overlays.
+///
+/// Pointers differ wherever the data is linked, so words which point into
+/// the reference overlay, or which are relocated, are masked. Data is
+/// located by `fingerprint`, which covers the longest run of words without
+/// any pointers, and confirmed by `digest`.
+#[serde_as]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DataSignature {
+    pub name: String,
+    /// The section the data is from, e.g. `.data`.
+    pub section: String,
+    /// The fingerprint of the words at `anchor`. Data words are hashed as
+    /// they are, without normalization.
+    pub fingerprint: Fingerprint,
+    pub anchor: usize,
+    pub size: usize,
+    /// The digest of every word of the data, with pointers masked.
+    pub digest: u64,
+    /// The masked bits of each word which holds a pointer.
+    pub relocations: Vec<RelocationMask>,
+    pub family: MIPSFamily,
+    /// The offset of each data symbol from the start of the data.
+    pub symbols: HashMap<String, usize>,
+}
+
+impl SerializeToYAML for DataSignature {
+    fn serialize_to_yaml_at_level<W: Write>(&self, level: usize, writer: &mut W) {
+        let indent = " ".repeat(level * 2);
+        writeln!(
+            writer,
+            "{}name: {}",
+            indent,
+            serde_yaml::to_string(&self.name).unwrap().trim()
+        )
+        .expect("data name serialization");
+        writeln!(
+            writer,
+            "{}section: {}",
+            indent,
+            serde_yaml::to_string(&self.section).unwrap().trim()
+        )
+        .expect("data section serialization");
+        writeln!(
+            writer,
+            "{}fingerprint: {}",
+            indent,
+            serde_yaml::to_string(&self.fingerprint).unwrap().trim()
+        )
+        .expect("data fingerprint serialization");
+        writeln!(writer, "{}anchor: 0x{:X}", indent, self.anchor)
+            .expect("data anchor serialization");
+        writeln!(writer, "{}size: 0x{:X}", indent, self.size).expect("data size serialization");
+        writeln!(writer, "{}digest: 0x{:016X}", indent, self.digest)
+            .expect("data digest serialization");
+        serialize_relocations(&indent, Some(&self.relocations), writer);
+        writeln!(
+            writer,
+            "{}family: {}",
+            indent,
+            serde_yaml::to_string(&self.family).unwrap().trim()
+        )
+        .expect("data family serialization");
+        serialize_symbols(&indent, "symbols", &self.symbols, writer);
+    }
+}
+
+/// A document of a fingerprint file.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Signature {
+    Segment(SegmentSignature),
+    Data(DataSignature),
+}
+
+// writes symbols in the order they appear
+fn serialize_symbols<W: Write>(
+    indent: &str,
+    key: &str,
+    symbols: &HashMap<String, usize>,
+    writer: &mut W,
+) {
+    writeln!(writer, "{}{}:", indent, key).expect("symbols key serialization");
+
+    let mut sorted_symbols: Vec<(&String, &usize)> = symbols.iter().collect();
+    sorted_symbols.sort_by_key(|(_, offset)| **offset);
+
+    for (symbol, offset) in sorted_symbols.iter() {
+        writeln!(
+            writer,
+            "{}  {}: 0x{:X}",
+            indent,
+            serde_yaml::to_string(&symbol).unwrap().trim(),
+            offset
+        )
+        .expect("symbol serialization");
+    }
+}
+
 #[serde_as]
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct RODataOffset {
     pub offset: usize,
     pub size: usize,
+    /// The jump tables found by following the segment's code.
+    #[serde(default)]
+    pub jump_tables: Vec<JumpTableOffset>,
+}
+
+/// A jump table in a bin, and the function which dispatches through it.
+#[serde_as]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct JumpTableOffset {
+    pub function: String,
+    pub offset: usize,
+    pub size: usize,
 }
 
 #[serde_as]
@@ -184,14 +466,52 @@ pub struct FunctionOffset {
     pub size: usize,
 }
 
+impl SerializeToYAML for FunctionOffset {
+    fn serialize_to_yaml_at_level<W: Write>(&self, level: usize, writer: &mut W) {
+        let indent = " ".repeat(level * 2);
+        writeln!(
+            writer,
+            "{}name: {}",
+            indent,
+            serde_yaml::to_string(&self.name).unwrap().trim()
+        )
+        .expect("function name serialization");
+        writeln!(writer, "{}offset: 0x{:X}", indent, self.offset)
+            .expect("function offset serialization");
+        writeln!(writer, "{}size: 0x{:X}", indent, self.size).expect("function size serialization");
+    }
+}
+
 #[serde_as]
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct SegmentOffset {
     pub name: String,
+    /// The section of a data match, e.g. `.data`. Code matches have none.
+    #[serde(default)]
+    pub section: Option<String>,
     pub offset: usize,
     pub size: usize,
     pub rodata: Option<RODataOffset>,
     pub symbols: HashMap<String, usize>,
+    /// The fraction of the segment's functions that were found, only
+    /// present for partial matches.
+    #[serde(default)]
+    pub coverage: Option<f64>,
+    /// Functions of a partial match which were not found.
+    #[serde(default)]
+    pub missing: Vec<String>,
+    /// Whether the segment's references to itself all moved by the same
+    /// amount. Code matches only.
+    #[serde(default)]
+    pub delta_check: Option<DeltaCheck>,
+    /// How far the references moved from the reference build, when they
+    /// are consistent.
+    #[serde(default)]
+    pub vram_delta: Option<isize>,
+    /// Functions outside of the segment which it calls, named after the
+    /// functions called in the reference.
+    #[serde(default)]
+    pub callees: HashMap<String, usize>,
 }
 
 impl SerializeToYAML for SegmentOffset {
@@ -204,32 +524,81 @@ impl SerializeToYAML for SegmentOffset {
             serde_yaml::to_string(&self.name).unwrap().trim()
         )
         .expect("segment name serialization");
+        if let Some(ref section) = self.section {
+            writeln!(
+                writer,
+                "{}section: {}",
+                indent,
+                serde_yaml::to_string(section).unwrap().trim()
+            )
+            .expect("segment section serialization");
+        }
         writeln!(writer, "{}offset: 0x{:X}", indent, self.offset)
             .expect("segment offset serialization");
         writeln!(writer, "{}size: 0x{:X}", indent, self.size).expect("segment size serialization");
 
+        if let Some(coverage) = self.coverage {
+            writeln!(writer, "{}coverage: {:.3}", indent, coverage)
+                .expect("segment coverage serialization");
+            writeln!(writer, "{}missing:", indent).expect("segment missing key serialization");
+            for name in self.missing.iter() {
+                writeln!(
+                    writer,
+                    "{}- {}",
+                    indent,
+                    serde_yaml::to_string(name).unwrap().trim()
+                )
+                .expect("segment missing function serialization");
+            }
+        }
+
+        if let Some(delta_check) = self.delta_check {
+            writeln!(
+                writer,
+                "{}delta_check: {}",
+                indent,
+                serde_yaml::to_string(&delta_check).unwrap().trim()
+            )
+            .expect("segment delta_check serialization");
+        }
+        if let Some(vram_delta) = self.vram_delta {
+            let sign = if vram_delta < 0 { "-" } else { "" };
+            writeln!(
+                writer,
+                "{}vram_delta: {}0x{:X}",
+                indent,
+                sign,
+                vram_delta.unsigned_abs()
+            )
+            .expect("segment vram_delta serialization");
+        }
+
         if let Some(ref rodata) = self.rodata {
             writeln!(writer, "{}rodata:", indent).expect("segment rodata key serialization");
             writeln!(writer, "{}  offset: 0x{:X}", indent, rodata.offset)
                 .expect("segment size serialization");
             writeln!(writer, "{}  size: 0x{:X}", indent, rodata.size)
                 .expect("segment size serialization");
+            if !rodata.jump_tables.is_empty() {
+                writeln!(writer, "{}  jump_tables:", indent)
+                    .expect("segment jump_tables key serialization");
+            }
+            for jump_table in rodata.jump_tables.iter() {
+                writeln!(
+                    writer,
+                    "{}  - {{ function: {}, offset: 0x{:X}, size: 0x{:X} }}",
+                    indent,
+                    serde_yaml::to_string(&jump_table.function).unwrap().trim(),
+                    jump_table.offset,
+                    jump_table.size
+                )
+                .expect("jump table serialization");
+            }
         }
 
-        writeln!(writer, "{}symbols:", indent).expect("segment symbols key serialization");
-
-        let mut sorted_symbols: Vec<(&String, &usize)> = self.symbols.iter().collect();
-        sorted_symbols.sort_by(|(_, a), (_, b)| a.cmp(b));
-
-        for (symbol, offset) in sorted_symbols.iter() {
-            writeln!(
-                writer,
-                "{}  {}: 0x{:X}",
-                indent,
-                serde_yaml::to_string(&symbol).unwrap().trim(),
-                offset
-            )
-            .expect("segment symbol serialization");
+        serialize_symbols(&indent, "symbols", &self.symbols, writer);
+        if !self.callees.is_empty() {
+            serialize_symbols(&indent, "callees", &self.callees, writer);
         }
     }
 }
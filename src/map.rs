@@ -1,17 +1,84 @@
 // SPDX-FileCopyrightText: © 2025 TTKB, LLC
 // SPDX-License-Identifier: BSD-3-CLAUSE
+use clap::ValueEnum;
 use itertools::Itertools;
 use mapfile_parser::MapFile;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::fs;
 use std::path::Path;
 
+pub mod gold;
+pub mod mwcc;
+
+/// The linker which wrote a map file.
+#[derive(ValueEnum, Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum MapFormat {
+    /// Determine the format from the map's contents
+    #[default]
+    Auto,
+    /// GNU ld
+    Gnu,
+    /// LLVM lld
+    Lld,
+    /// GNU gold
+    Gold,
+    /// Metrowerks CodeWarrior
+    Mwcc,
+}
+
+impl MapFormat {
+    /// Determines the format of a map from its contents. GNU ld is assumed
+    /// when no other format is recognized.
+    pub fn detect(contents: &str) -> Self {
+        let lld_header =
+            regex::Regex::new(r"\s+VMA\s+LMA\s+Size\s+Align\s+Out\s+In\s+Symbol").unwrap();
+
+        if lld_header.is_match(contents) {
+            Self::Lld
+        } else if mwcc::is_mwcc_map(contents) {
+            Self::Mwcc
+        } else if gold::is_gold_map(contents) {
+            Self::Gold
+        } else {
+            Self::Gnu
+        }
+    }
+}
+
+/// Reads a map file written in `format`.
+pub fn read_map_file(map_file_path: &Path, format: MapFormat) -> MapFile {
+    let contents = fs::read_to_string(map_file_path).expect("Could not read map file.");
+
+    let format = match format {
+        MapFormat::Auto => MapFormat::detect(&contents),
+        format => format,
+    };
+
+    match format {
+        MapFormat::Gnu | MapFormat::Auto => {
+            let mut map_file = MapFile::new();
+            map_file.parse_map_contents_gnu(&contents);
+            map_file
+        }
+        MapFormat::Lld => {
+            let mut map_file = MapFile::new();
+            map_file.parse_map_contents_lld(&contents);
+            map_file
+        }
+        MapFormat::Gold => gold::parse_map_contents(&contents),
+        MapFormat::Mwcc => mwcc::parse_map_contents(&contents),
+    }
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct FunctionEntry {
     pub name: String,
     pub offset: usize,
     pub vram: usize,
     pub size: usize,
+    /// The symbol had no size, and `size` extends to the next symbol.
+    #[serde(default)]
+    pub size_inferred: bool,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -32,18 +99,45 @@ pub struct ObjectMap {
     pub text_symbols: Vec<FunctionEntry>,
 }
 
+// the name of an object file without its directory or extensions
+fn object_stem(path: &str) -> &str {
+    // this can be done with experimental file_prefix
+    // function, but replicating logic here for stable
+    Path::new(path)
+        .file_name()
+        .expect("object name")
+        .to_str()
+        .unwrap()
+        .trim_end_matches(".c.o")
+        .trim_end_matches(".s.o")
+        .trim_end_matches(".o")
+}
+
+/// Splits an archive member path, like `/psyq/lib/libapi.a(a07.o)`, into
+/// the archive and the member.
+pub fn archive_member(path: &str) -> Option<(&str, &str)> {
+    let (archive, member) = path.strip_suffix(')')?.split_once(".a(")?;
+    if archive.is_empty() || member.is_empty() {
+        return None;
+    }
+    Some((archive, member))
+}
+
+// objects linked directly, or pulled from static libraries
+fn is_object_path(path: &str) -> bool {
+    path.ends_with(".o") || archive_member(path).is_some()
+}
+
 impl ObjectMap {
-    pub fn name(&self) -> &str {
-        // this can be done with experimental file_prefix
-        // function, but replicating logic here for stable
-        Path::new(&self.object)
-            .file_name()
-            .expect("object name")
-            .to_str()
-            .unwrap()
-            .trim_end_matches(".c.o")
-            .trim_end_matches(".s.o")
-            .trim_end_matches(".o")
+    /// The segment name for the object. Archive members are named after
+    /// both the archive and the member, e.g. `libgs/sprite`.
+    pub fn name(&self) -> String {
+        match archive_member(&self.object) {
+            Some((archive, member)) => {
+                format!("{}/{}", object_stem(archive), object_stem(member))
+            }
+            None => object_stem(&self.object).to_string(),
+        }
     }
 
     // determine if address is within a any segment functions (exclusive)
@@ -64,20 +158,36 @@ fn symbols_to_segment_symbols(
     vram: usize,
     vrom_offset: usize,
     offset: usize,
+    file_vrom: usize,
     size: usize,
     symbols: &[FunctionEntry],
 ) -> Vec<FunctionEntry> {
-    let mut entries: Vec<FunctionEntry> = symbols
+    let in_file = symbols
         .iter()
         .filter(|entry| entry.vram >= offset && entry.vram < (offset + size))
+        .collect::<Vec<_>>();
+
+    // overlays can share the same addresses. when the load addresses of
+    // the symbols agree with the map, only those loaded with the file are
+    // kept
+    let loaded = in_file
+        .iter()
+        .filter(|entry| entry.offset >= file_vrom && entry.offset < (file_vrom + size))
+        .copied()
+        .collect::<Vec<_>>();
+    let in_file = if loaded.is_empty() { in_file } else { loaded };
+
+    let mut entries: Vec<FunctionEntry> = in_file
+        .into_iter()
         .map(|entry| FunctionEntry {
             name: entry.name.clone(),
             vram: entry.vram,
             offset: entry.vram - vram + vrom_offset,
             size: entry.size,
+            size_inferred: entry.size_inferred,
         })
         .collect();
-    entries.sort_by(|a, b| a.vram.cmp(&b.vram));
+    entries.sort_by_key(|entry| entry.vram);
     entries
 }
 
@@ -94,8 +204,7 @@ pub fn read_rodata(map_file: &MapFile, object: &String) -> Option<SegmentInfo> {
                 .filter(|file| file.filepath.to_str().unwrap() == object)
                 .chunk_by(|file| file.filepath.clone())
                 .into_iter()
-                .map(|(filepath, files)| {
-                    // println!("file: {}", filepath.display());
+                .map(|(_, files)| {
                     // println!("segment: {:?}", segment);
                     let files = files.collect::<Vec<_>>();
                     let first = files.first().unwrap();
@@ -118,13 +227,15 @@ pub fn read_rodata(map_file: &MapFile, object: &String) -> Option<SegmentInfo> {
 
 // Params:
 //   - map_file: path
+//   - format: the linker which wrote the map file
 //   - section_type: type name like ".text", ".rodata", etc.
 pub fn read_segments(
     map_file_path: &Path,
+    format: MapFormat,
     section_type: &str,
     function_symbols: Vec<FunctionEntry>,
 ) -> Vec<ObjectMap> {
-    let map_file = MapFile::new_from_map_file(map_file_path);
+    let map_file = read_map_file(map_file_path, format);
 
     map_file
         .filter_by_section_type(section_type)
@@ -135,7 +246,7 @@ pub fn read_segments(
                 .files_list
                 .iter()
                 // .inspect(|file| println!("file: {:?}", file))
-                .filter(|file| file.filepath.to_str().unwrap().ends_with(".o"))
+                .filter(|file| is_object_path(file.filepath.to_str().unwrap()))
                 .chunk_by(|file| file.filepath.clone())
                 .into_iter()
                 .map(|(filepath, files)| {
@@ -161,6 +272,7 @@ pub fn read_segments(
                                 segment.vram as usize,
                                 segment.vrom as usize,
                                 file.vram as usize,
+                                file.vrom.unwrap_or(file.vram) as usize,
                                 file.size as usize,
                                 &function_symbols,
                             )
@@ -186,3 +298,65 @@ pub fn read_segments(
         })
         .collect::<Vec<_>>()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object_map(object: &str) -> ObjectMap {
+        ObjectMap {
+            object: object.to_string(),
+            offset: 0,
+            vram: 0,
+            vrom: 0,
+            size: 0,
+            rodata: None,
+            text_symbols: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_archive_member() {
+        assert_eq!(
+            archive_member("/psyq/lib/libapi.a(a07.o)"),
+            Some(("/psyq/lib/libapi", "a07.o"))
+        );
+        assert_eq!(
+            archive_member("libgs.a(sprite.o)"),
+            Some(("libgs", "sprite.o"))
+        );
+        assert_eq!(archive_member("build/src/sword.c.o"), None);
+        assert_eq!(archive_member("libgs.a()"), None);
+
+        assert!(is_object_path("libgs.a(sprite.o)"));
+        assert!(is_object_path("build/src/sword.c.o"));
+        assert!(!is_object_path("build/src/sword.ld"));
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            MapFormat::detect("     VMA      LMA     Size Align Out     In      Symbol\n"),
+            MapFormat::Lld
+        );
+        assert_eq!(
+            MapFormat::detect("Memory map\n\n.text 0x80010000 0x8c\n"),
+            MapFormat::Gold
+        );
+        assert_eq!(
+            MapFormat::detect("Link map of main\n\n.text section layout\n"),
+            MapFormat::Mwcc
+        );
+        assert_eq!(
+            MapFormat::detect("Memory Configuration\n\nLinker script and memory map\n"),
+            MapFormat::Gnu
+        );
+    }
+
+    #[test]
+    fn test_name() {
+        assert_eq!(object_map("build/us/src/sword.c.o").name(), "sword");
+        assert_eq!(object_map("libgs.a(sprite.o)").name(), "libgs/sprite");
+        assert_eq!(object_map("/psyq/lib/libapi.a(a07.o)").name(), "libapi/a07");
+    }
+}
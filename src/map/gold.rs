@@ -0,0 +1,77 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+use mapfile_parser::MapFile;
+use regex::Regex;
+
+/// Determines if `contents` are a map written by GNU gold. gold titles its
+/// memory map "Memory map" where GNU ld writes "Linker script and memory
+/// map".
+pub fn is_gold_map(contents: &str) -> bool {
+    let header = Regex::new(r"(?m)^Memory map\s*$").unwrap();
+    header.is_match(contents) && !contents.contains("Linker script and memory map")
+}
+
+/// Rewrites a gold map so it can be read as a GNU ld map.
+///
+/// The two formats only differ in that gold does not list a load address
+/// for output sections. Those are given the section's address, so offsets
+/// are relative to the start of the section.
+pub fn to_gnu_map(contents: &str) -> String {
+    let section = Regex::new(r"^(\S*\s+(0x[0-9A-Fa-f]+)\s+0x[0-9A-Fa-f]+)\s*$").unwrap();
+
+    contents
+        .lines()
+        .map(|line| match section.captures(line) {
+            Some(captures) => format!("{} load address {}", &captures[1], &captures[2]),
+            None => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parses the contents of a gold map.
+pub fn parse_map_contents(contents: &str) -> MapFile {
+    let mut map_file = MapFile::new();
+    map_file.parse_map_contents_gnu(&to_gnu_map(contents));
+
+    // the GNU reader accumulates offsets from file sizes, which drifts when
+    // alignment padding is not listed as `*fill*`. every address here is
+    // its own load address, so offsets can be set exactly.
+    for segment in map_file.segments_list.iter_mut() {
+        for file in segment.files_list.iter_mut() {
+            if file.vrom.is_some() {
+                file.vrom = Some(segment.vrom + (file.vram - segment.vram));
+            }
+        }
+    }
+
+    map_file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_gnu_map() {
+        let gold = "Memory map\n\
+                    \n\
+                    .text           0x80010000       0x8c\n \
+                    *(.text .stub .text.*)\n \
+                    .text          0x80010000       0x1c game.o\n                \
+                    0x80010000                main\n";
+
+        assert!(is_gold_map(gold));
+        assert!(!is_gold_map("Linker script and memory map\n"));
+
+        assert_eq!(
+            to_gnu_map(gold),
+            "Memory map\n\
+             \n\
+             .text           0x80010000       0x8c load address 0x80010000\n \
+             *(.text .stub .text.*)\n \
+             .text          0x80010000       0x1c game.o\n                \
+             0x80010000                main"
+        );
+    }
+}
@@ -0,0 +1,154 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+use mapfile_parser::{File, MapFile, Segment, Symbol};
+use regex::Regex;
+use std::path::PathBuf;
+
+/// Determines if `contents` are a map written by the Metrowerks
+/// CodeWarrior (MWCC) linker.
+pub fn is_mwcc_map(contents: &str) -> bool {
+    section_header_regex().is_match(contents)
+}
+
+fn section_header_regex() -> Regex {
+    Regex::new(r"(?m)^(?P<section>\S+) section layout\s*$").unwrap()
+}
+
+// the object an entry belongs to. members of archives are listed as
+// `<archive> <member>` and are named like they are in a GNU map
+fn object_path(object: &str) -> PathBuf {
+    let parts = object.split_whitespace().collect::<Vec<_>>();
+    match parts.as_slice() {
+        [archive, member] => PathBuf::from(format!("{}({})", archive, member)),
+        _ => PathBuf::from(object.trim()),
+    }
+}
+
+/// Parses the contents of a CodeWarrior map.
+///
+/// Each `<section> section layout` table becomes a segment. A row whose
+/// symbol is the section itself starts an object's part of the section,
+/// and the rows which follow are its symbols:
+///
+/// ```pre
+/// .text section layout
+///   Starting        Virtual  File
+///   address  Size   address  offset
+///   ---------------------------------
+///   00000000 00001c 80010000 000000c0  1 .text   main.o
+///   00000000 00001c 80010000 000000c0  4 main    main.o
+/// ```
+///
+/// The file offset column is optional. Without it, offsets are relative
+/// to the start of the section.
+pub fn parse_map_contents(contents: &str) -> MapFile {
+    let header = section_header_regex();
+    let entry = Regex::new(
+        r"^\s+(?P<start>[0-9A-Fa-f]+)\s+(?P<size>[0-9A-Fa-f]+)\s+(?P<vram>[0-9A-Fa-f]+)(\s+(?P<offset>[0-9A-Fa-f]{8}))?\s+(?P<align>\d+)\s+(?P<name>\S+)\s+(?P<object>.+?)\s*$",
+    )
+    .unwrap();
+    let parse_hex = |s: &str| u64::from_str_radix(s, 16).unwrap_or(0);
+
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut section = String::new();
+
+    for line in contents.lines() {
+        if let Some(captures) = header.captures(line) {
+            section = captures["section"].to_string();
+            segments.push(Segment::new_default(section.clone(), 0, 0, 0));
+            continue;
+        }
+
+        let Some(captures) = entry.captures(line) else {
+            continue;
+        };
+        let Some(segment) = segments.last_mut() else {
+            continue;
+        };
+
+        let vram = parse_hex(&captures["vram"]);
+        let size = parse_hex(&captures["size"]);
+        let vrom = captures
+            .name("offset")
+            .map(|offset| parse_hex(offset.as_str()))
+            .unwrap_or(vram);
+        let name = &captures["name"];
+        let filepath = object_path(&captures["object"]);
+
+        if name == section {
+            if size == 0 {
+                continue;
+            }
+            if segment.files_list.is_empty() {
+                segment.vram = vram;
+                segment.vrom = vrom;
+            }
+            segment.size = (vram + size).saturating_sub(segment.vram);
+
+            let mut file = File::new_default(filepath, vram, size, &section);
+            file.vrom = Some(vrom);
+            segment.files_list.push(file);
+        } else if let Some(file) = segment
+            .files_list
+            .last_mut()
+            .filter(|file| file.filepath == filepath)
+        {
+            let mut symbol = Symbol::new_default(name.to_string(), vram);
+            symbol.size = Some(size);
+            symbol.vrom = Some(vrom);
+            file.symbols.push(symbol);
+        }
+    }
+
+    let mut map_file = MapFile::new();
+    map_file.segments_list = segments
+        .into_iter()
+        .filter(|segment| !segment.files_list.is_empty())
+        .collect();
+    map_file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_map_contents() {
+        let map = "Link map of main\n\
+                   \n\
+                   .text section layout\n  \
+                     Starting        Virtual  File\n  \
+                     address  Size   address  offset\n  \
+                     ---------------------------------\n  \
+                     00000000 00001c 80010000 000000c0  1 .text \tmain.o \n  \
+                     00000000 00001c 80010000 000000c0  4 main \tmain.o \n  \
+                     UNUSED   000010 ........ unused main.o \n  \
+                     00000020 000008 80010020 000000e0  1 .text \tlibc.a memset.o \n  \
+                     00000020 000008 80010020 000000e0  4 memset \tlibc.a memset.o \n\
+                   \n\
+                   .data section layout\n  \
+                     Starting        Virtual\n  \
+                     address  Size   address\n  \
+                     -----------------------\n  \
+                     00000000 000004 80010040  1 .data \tmain.o \n";
+
+        assert!(is_mwcc_map(map));
+        let map_file = parse_map_contents(map);
+        assert_eq!(map_file.segments_list.len(), 2);
+
+        let text = &map_file.segments_list[0];
+        assert_eq!(text.name, ".text");
+        assert_eq!((text.vram, text.vrom, text.size), (0x80010000, 0xC0, 0x28));
+        assert_eq!(text.files_list.len(), 2);
+
+        let memset = &text.files_list[1];
+        assert_eq!(memset.filepath, PathBuf::from("libc.a(memset.o)"));
+        assert_eq!((memset.vram, memset.vrom), (0x80010020, Some(0xE0)));
+        assert_eq!(memset.symbols.len(), 1);
+        assert_eq!(memset.symbols[0].name, "memset");
+
+        // without file offsets, offsets are addresses
+        let data = &map_file.segments_list[1];
+        assert_eq!(data.files_list[0].vrom, Some(0x80010040));
+    }
+}
@@ -0,0 +1,193 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+use elf::endian::{AnyEndian, EndianParse};
+use elf::ElfBytes;
+
+// ECOFF symbolic header magic
+const MAGIC: u16 = 0x7009;
+
+const HDRR_SIZE: usize = 96;
+const FDR_SIZE: usize = 72;
+const PDR_SIZE: usize = 52;
+const SYMR_SIZE: usize = 12;
+
+// symbol types
+const ST_PROC: u32 = 6;
+const ST_BLOCK: u32 = 7;
+const ST_END: u32 = 8;
+const ST_STATIC_PROC: u32 = 14;
+
+/// A procedure from an `.mdebug` procedure descriptor.
+#[derive(Debug, PartialEq)]
+pub struct Procedure {
+    pub name: String,
+    pub vram: usize,
+    /// The size of the procedure, when its end symbol records one.
+    pub size: Option<usize>,
+    /// `static` procedures are not visible to the linker, and are often
+    /// missing from map files and the ELF symbol table.
+    pub is_static: bool,
+}
+
+/// A source file from an `.mdebug` file descriptor, with its procedures
+/// in address order.
+#[derive(Debug, PartialEq)]
+pub struct SourceFile {
+    pub name: String,
+    pub vram: usize,
+    pub procedures: Vec<Procedure>,
+}
+
+// a local symbol: name offset, value, and type
+struct Symbol {
+    iss: usize,
+    value: usize,
+    st: u32,
+}
+
+// reads fields in the byte order of the ELF file
+struct Reader<'a> {
+    data: &'a [u8],
+    endian: AnyEndian,
+}
+
+impl Reader<'_> {
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let mut offset = offset;
+        self.endian.parse_u16_at(&mut offset, self.data).ok()
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let mut offset = offset;
+        self.endian.parse_u32_at(&mut offset, self.data).ok()
+    }
+
+    fn usize(&self, offset: usize) -> Option<usize> {
+        self.u32(offset).map(|n| n as usize)
+    }
+
+    fn str(&self, offset: usize) -> Option<String> {
+        let bytes = self.data.get(offset..)?;
+        let end = bytes.iter().position(|b| *b == 0)?;
+        Some(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+
+    fn symbol(&self, offset: usize) -> Option<Symbol> {
+        let bits = self.u32(offset + 8)?;
+        // the bit fields are packed from the other end on big endian files
+        let st = if self.endian.is_little() {
+            bits & 0x3F
+        } else {
+            bits >> 26
+        };
+        Some(Symbol {
+            iss: self.usize(offset)?,
+            value: self.usize(offset + 4)?,
+            st,
+        })
+    }
+}
+
+/// Reads the file and procedure descriptors of the `.mdebug` (ECOFF
+/// symbolic debug) section of `file`. Empty if there is no such section.
+///
+/// Offsets in the symbolic header are file offsets, as they are in the
+/// ELF files written by IDO and GCC.
+pub fn source_files(file: &ElfBytes<AnyEndian>) -> Vec<SourceFile> {
+    let Ok(Some(shdr)) = file.section_header_by_name(".mdebug") else {
+        return Vec::new();
+    };
+    let Ok((data, _)) = file.section_data(&shdr) else {
+        return Vec::new();
+    };
+    read_source_files(data, shdr.sh_offset as usize, file.ehdr.endianness).unwrap_or_default()
+}
+
+fn read_source_files(
+    data: &[u8],
+    section_offset: usize,
+    endian: AnyEndian,
+) -> Option<Vec<SourceFile>> {
+    let reader = Reader { data, endian };
+    if data.len() < HDRR_SIZE || reader.u16(0)? != MAGIC {
+        return None;
+    }
+
+    // a table's location within the section
+    let table =
+        |field: usize| -> Option<usize> { reader.usize(field)?.checked_sub(section_offset) };
+
+    let pd_count = reader.usize(24)?;
+    let pd_offset = if pd_count > 0 { table(28)? } else { 0 };
+    let sym_offset = table(36)?;
+    let ss_offset = table(60)?;
+    let fd_count = reader.usize(72)?;
+    let fd_offset = table(76)?;
+
+    let mut files = Vec::with_capacity(fd_count);
+    for i in 0..fd_count {
+        let fdr = fd_offset + i * FDR_SIZE;
+        let vram = reader.usize(fdr)?;
+        let rss = reader.usize(fdr + 4)?;
+        let iss_base = reader.usize(fdr + 8)?;
+        let isym_base = reader.usize(fdr + 16)?;
+        let csym = reader.usize(fdr + 20)?;
+        let ipd_first = reader.u16(fdr + 40)? as usize;
+        let cpd = reader.u16(fdr + 42)? as usize;
+
+        let strings = ss_offset + iss_base;
+        let name = reader.str(strings + rss).unwrap_or_default();
+
+        let mut procedures = Vec::with_capacity(cpd);
+        for j in ipd_first..(ipd_first + cpd).min(pd_count) {
+            let pdr = pd_offset + j * PDR_SIZE;
+            let isym = reader.usize(pdr + 4)?;
+            if isym >= csym {
+                continue;
+            }
+
+            let symbol_at = |k: usize| reader.symbol(sym_offset + (isym_base + k) * SYMR_SIZE);
+            let Some(symbol) = symbol_at(isym) else {
+                continue;
+            };
+            if symbol.st != ST_PROC && symbol.st != ST_STATIC_PROC {
+                continue;
+            }
+
+            // the value of the procedure's end symbol is its size
+            let mut depth = 0;
+            let mut size = None;
+            for k in (isym + 1)..csym {
+                let Some(inner) = symbol_at(k) else {
+                    break;
+                };
+                match inner.st {
+                    ST_BLOCK => depth += 1,
+                    ST_END if depth == 0 => {
+                        size = Some(inner.value);
+                        break;
+                    }
+                    ST_END => depth -= 1,
+                    ST_PROC | ST_STATIC_PROC => break,
+                    _ => (),
+                }
+            }
+
+            procedures.push(Procedure {
+                name: reader.str(strings + symbol.iss).unwrap_or_default(),
+                vram: symbol.value,
+                size,
+                is_static: symbol.st == ST_STATIC_PROC,
+            });
+        }
+        procedures.sort_by_key(|procedure| procedure.vram);
+
+        files.push(SourceFile {
+            name,
+            vram,
+            procedures,
+        });
+    }
+
+    Some(files)
+}
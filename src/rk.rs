@@ -1,8 +1,10 @@
 // SPDX-FileCopyrightText: © 2025 TTKB, LLC
 // SPDX-License-Identifier: BSD-3-CLAUSE
+use std::collections::{BTreeMap, HashMap};
 use std::hash::Hasher;
 
 use crate::arch::mips;
+use crate::image::NormalizedImage;
 use crate::MIPSFamily;
 
 /// A Rabin-Karp rolling hasher implementation.
@@ -44,7 +46,7 @@ impl RabinKarpMIPSHasher {
     pub fn new_with_modulus(family: MIPSFamily, modulus: u64) -> Self {
         Self {
             radix: Self::DEFAULT_RADIX,
-            modulus: modulus,
+            modulus,
             family,
             hash: 0,
         }
@@ -55,27 +57,145 @@ impl RabinKarpMIPSHasher {
     ///    size - size of the machine code in bytes that produced the hash
     ///    bytes - haystack of bytes to search
     pub fn find(&self, needle: u64, size: usize, bytes: &[u8]) -> Option<usize> {
-        if size > bytes.len() {
-            return None;
-        } else if size == 0 {
-            return Some(0);
+        self.find_all(needle, size, bytes).next()
+    }
+
+    /// Returns an iterator over the offset of every occurrence of `needle`
+    /// in `bytes`, in ascending order. Occurrences may overlap.
+    ///
+    /// Parameters:
+    ///    needle - RK hash like one produced by this hasher
+    ///    size - size of the machine code in bytes that produced the hash
+    ///    bytes - haystack of bytes to search
+    pub fn find_all<'a>(&self, needle: u64, size: usize, bytes: &'a [u8]) -> FindAll<'a> {
+        // removal hash
+        let mut rm: u64 = 1;
+        for _ in 1..(size / 4) {
+            rm = (self.radix * rm) % self.modulus;
         }
 
-        // starting hash
-        let mut hash = self.hash_be_mips_bytes(0, &bytes[..size]);
+        FindAll {
+            radix: self.radix,
+            modulus: self.modulus,
+            family: self.family,
+            needle,
+            size,
+            bytes,
+            rm,
+            hash: 0,
+            position: 0,
+        }
+    }
 
-        if hash == needle {
-            return Some(0);
+    /// Returns an iterator over the byte offset of every occurrence of
+    /// `needle` in already normalized `words`, such as a window of a
+    /// [`NormalizedImage`].
+    pub fn find_all_words<'a>(
+        &self,
+        needle: u64,
+        size: usize,
+        words: &'a [u32],
+    ) -> impl Iterator<Item = usize> + 'a {
+        self.rolling_hashes(words, size / 4)
+            .filter(move |(_, hash)| *hash == needle)
+            .map(|(offset, _)| offset)
+    }
+
+    /// Searches `image` for every fingerprint in `set`.
+    ///
+    /// Each distinct fingerprint size is checked in a single rolling pass,
+    /// so the cost depends on the number of distinct sizes rather than the
+    /// number of fingerprints. Every hit is returned, ordered by offset.
+    pub fn find_set(&self, set: &FingerprintSet, image: &NormalizedImage) -> Vec<PatternMatch> {
+        self.find_set_words(set, image.words())
+    }
+
+    /// Searches `words` for every fingerprint in `set`, like [`find_set`].
+    /// The words are hashed as they are, so they may be data rather than
+    /// normalized instructions.
+    ///
+    /// [`find_set`]: Self::find_set
+    pub fn find_set_words(&self, set: &FingerprintSet, words: &[u32]) -> Vec<PatternMatch> {
+        let mut matches = Vec::new();
+        for (size, needles) in set.patterns.iter() {
+            for (offset, hash) in self.rolling_hashes(words, size / 4) {
+                if let Some(ids) = needles.get(&hash) {
+                    matches.extend(ids.iter().map(|&id| PatternMatch { id, offset }));
+                }
+            }
         }
 
+        matches.sort_by_key(|m| (m.offset, m.id));
+        matches
+    }
+
+    /// Returns an iterator over the hash of every `count` instruction
+    /// window of already normalized `words`, along with the byte offset
+    /// of the start of that window.
+    pub fn rolling_hashes<'a>(&self, words: &'a [u32], count: usize) -> RollingHashes<'a> {
         // removal hash
-        let rm = {
-            let mut rm: u64 = 1;
-            for _ in 1..(size / 4) {
-                rm = (self.radix * rm) % self.modulus;
-            }
-            rm
-        };
+        let mut rm: u64 = 1;
+        for _ in 1..count {
+            rm = (self.radix * rm) % self.modulus;
+        }
+
+        RollingHashes {
+            radix: self.radix,
+            modulus: self.modulus,
+            words,
+            count,
+            rm,
+            hash: 0,
+            position: 0,
+        }
+    }
+
+    fn horner_hash(&self, acc: u64, s: u32) -> u64 {
+        horner_hash(acc, s, self.radix, self.modulus)
+    }
+
+    fn hash_be_mips_bytes(&self, hash: u64, bytes: &[u8]) -> u64 {
+        if !bytes.len().is_multiple_of(4) {
+            panic!("misaligned block");
+        }
+
+        bytes
+            .chunks(4)
+            .map(|ins| mips::bytes_to_normalized_instruction(ins, self.family))
+            .fold(hash, |acc, masked_ins| self.horner_hash(acc, masked_ins))
+    }
+}
+
+/// Iterator created by [`RabinKarpMIPSHasher::find_all`].
+#[derive(Debug)]
+pub struct FindAll<'a> {
+    radix: u64,
+    modulus: u64,
+    family: MIPSFamily,
+    needle: u64,
+    size: usize,
+    bytes: &'a [u8],
+    rm: u64,
+    hash: u64,
+    position: usize,
+}
+
+impl FindAll<'_> {
+    fn instruction(&self, offset: usize) -> u32 {
+        mips::bytes_to_normalized_instruction(&self.bytes[offset..(offset + 4)], self.family)
+    }
+}
+
+impl Iterator for FindAll<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        // an empty needle is only found at the start of the haystack
+        if self.size == 0 {
+            let start = self.position;
+            self.position += 4;
+            return if start == 0 { Some(0) } else { None };
+        }
 
         // march through the remainder of the slice along
         // with the beginning of the slice to pop off the
@@ -94,44 +214,130 @@ impl RabinKarpMIPSHasher {
         //    ├──────────┤
         //  first       new
 
-        let instruction_position = bytes[size..]
-            .chunks(4)
-            .map(|ins| mips::bytes_to_normalized_instruction(&ins, self.family))
-            .zip(
-                bytes
+        loop {
+            let start = self.position;
+            let end = start + self.size;
+            if end > self.bytes.len() {
+                return None;
+            }
+
+            if start == 0 {
+                // starting hash
+                self.hash = self.bytes[..self.size]
                     .chunks(4)
-                    .map(|ins| mips::bytes_to_normalized_instruction(&ins, self.family)),
-            )
-            .map(|(new, first)| {
+                    .map(|ins| mips::bytes_to_normalized_instruction(ins, self.family))
+                    .fold(0, |acc, ins| {
+                        horner_hash(acc, ins, self.radix, self.modulus)
+                    });
+            } else {
                 // remove last instruction
-                hash = (hash + self.modulus - (rm * first as u64) % self.modulus) % self.modulus;
-                hash = self.horner_hash(hash, new);
-                hash
-            })
-            .position(|hash| hash == needle);
-
-        match instruction_position {
-            // a found position must be one after pos because
-            // the 0th position in the remaining slice is 1 after
-            // the position of the `bytes` slice.
-            Some(pos) => Some((pos + 1) * 4),
-            None => None,
+                let first = self.instruction(start - 4) as u64;
+                let new = self.instruction(end - 4);
+                self.hash =
+                    (self.hash + self.modulus - (self.rm * first) % self.modulus) % self.modulus;
+                self.hash = horner_hash(self.hash, new, self.radix, self.modulus);
+            }
+
+            self.position += 4;
+
+            if self.hash == self.needle {
+                return Some(start);
+            }
         }
     }
+}
 
-    fn horner_hash(&self, acc: u64, s: u32) -> u64 {
-        horner_hash(acc, s, self.radix, self.modulus)
+/// A hit reported by [`RabinKarpMIPSHasher::find_set`]. `id` is the
+/// identifier the fingerprint was inserted into the set with and `offset`
+/// is the byte offset of the match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PatternMatch {
+    pub id: usize,
+    pub offset: usize,
+}
+
+/// A collection of fingerprints grouped by size so that all of them can
+/// be searched for with one rolling pass per distinct size.
+#[derive(Debug, Default)]
+pub struct FingerprintSet {
+    // size in bytes -> hash -> ids of every fingerprint with that hash
+    patterns: BTreeMap<usize, HashMap<u64, Vec<usize>>>,
+    len: usize,
+}
+
+impl FingerprintSet {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    fn hash_be_mips_bytes(&self, hash: u64, bytes: &[u8]) -> u64 {
-        if (bytes.len() % 4) != 0 {
-            panic!("misaligned block");
+    /// Adds a fingerprint of `size` bytes with the hash `hash`. Matches
+    /// for it will be reported with `id`.
+    pub fn insert(&mut self, id: usize, hash: u64, size: usize) {
+        self.patterns
+            .entry(size)
+            .or_default()
+            .entry(hash)
+            .or_default()
+            .push(id);
+        self.len += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Iterator created by [`RabinKarpMIPSHasher::rolling_hashes`].
+///
+/// Yields `(offset, hash)` for each window. A zero length window only
+/// matches at the start of the haystack, mirroring `find`.
+#[derive(Debug)]
+pub struct RollingHashes<'a> {
+    radix: u64,
+    modulus: u64,
+    words: &'a [u32],
+    count: usize,
+    rm: u64,
+    hash: u64,
+    position: usize,
+}
+
+impl Iterator for RollingHashes<'_> {
+    type Item = (usize, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let position = self.position;
+
+        if self.count == 0 {
+            if position > 0 {
+                return None;
+            }
+            self.position += 1;
+            return Some((0, 0));
         }
 
-        bytes
-            .chunks(4)
-            .map(|ins| mips::bytes_to_normalized_instruction(&ins, self.family))
-            .fold(hash, |acc, masked_ins| self.horner_hash(acc, masked_ins))
+        if position + self.count > self.words.len() {
+            return None;
+        }
+
+        if position == 0 {
+            self.hash = self.words[..self.count]
+                .iter()
+                .fold(0, |acc, &w| horner_hash(acc, w, self.radix, self.modulus));
+        } else {
+            let first = self.words[position - 1] as u64;
+            let new = self.words[position + self.count - 1];
+            self.hash =
+                (self.hash + self.modulus - (self.rm * first) % self.modulus) % self.modulus;
+            self.hash = horner_hash(self.hash, new, self.radix, self.modulus);
+        }
+
+        self.position += 1;
+        Some((position * 4, self.hash))
     }
 }
 
@@ -142,7 +348,7 @@ pub fn horner_hash(acc: u64, s: u32, radix: u64, q: u64) -> u64 {
 
 impl Hasher for RabinKarpMIPSHasher {
     fn write(&mut self, bytes: &[u8]) {
-        if (bytes.len() % 4) != 0 {
+        if !bytes.len().is_multiple_of(4) {
             panic!("misaligned block");
         }
 
@@ -213,7 +419,6 @@ mod test {
         // additional NOPs don't effect the fletcher checksum
         hasher.write(&JR_RA_NOPS[0..12]);
         assert_eq!(hasher.finish(), 0x3E00008);
-        hasher.hash = 0;
     }
 
     const RETURN_ZERO_NOPS: [u8; 32] = [
@@ -231,7 +436,6 @@ mod test {
     use crate::scan::{self};
     use crate::Options;
     use std::io::Cursor;
-    use std::io::Write;
 
     #[test]
     fn test_find() {
@@ -262,4 +466,64 @@ mod test {
 
         assert_eq!(hasher.find(h, 4, &RETURN_ZERO_NOPS), Some(12));
     }
+
+    #[test]
+    fn test_find_all() {
+        let hasher = RabinKarpMIPSHasher::new(MIPSFamily::R3000GTE);
+
+        // two consecutive nops, overlapping occurrences are all reported
+        assert_eq!(
+            hasher.find_all(0, 8, &JR_RA_NOPS).collect::<Vec<_>>(),
+            vec![4, 8, 12, 16]
+        );
+        assert_eq!(
+            hasher.find_all(0, 8, &RETURN_ZERO_NOPS).collect::<Vec<_>>(),
+            vec![0, 4, 20, 24]
+        );
+
+        assert_eq!(
+            hasher
+                .find_all(0x41E00088, 8, &JR_RA_NOPS)
+                .collect::<Vec<_>>(),
+            vec![0]
+        );
+        assert_eq!(
+            hasher.find_all(0, 0, &JR_RA_NOPS).collect::<Vec<_>>(),
+            vec![0]
+        );
+        assert_eq!(hasher.find_all(0, 28, &JR_RA_NOPS).next(), None);
+    }
+
+    #[test]
+    fn test_find_set() {
+        let hasher = RabinKarpMIPSHasher::new(MIPSFamily::R3000GTE);
+
+        let mut set = FingerprintSet::new();
+        set.insert(0, 0x41E00088, 8);
+        set.insert(1, 0x5FE0094C, 12);
+        // two consecutive nops
+        set.insert(2, 0, 8);
+        // never present
+        set.insert(3, 0x12345678, 8);
+        assert_eq!(set.len(), 4);
+
+        let image = NormalizedImage::new(&JR_RA_NOPS, MIPSFamily::R3000GTE);
+        let matches = hasher.find_set(&set, &image);
+        let expected = [(0, 0), (1, 0), (2, 4), (2, 8), (2, 12), (2, 16)]
+            .iter()
+            .map(|&(id, offset)| PatternMatch { id, offset })
+            .collect::<Vec<_>>();
+        assert_eq!(matches, expected);
+
+        // agrees with single pattern searches
+        for m in matches.iter().filter(|m| m.id != 2) {
+            let size = if m.id == 0 { 8 } else { 12 };
+            let needle = if m.id == 0 { 0x41E00088 } else { 0x5FE0094C };
+            assert_eq!(hasher.find(needle, size, &JR_RA_NOPS), Some(m.offset));
+            assert_eq!(
+                hasher.find_all_words(needle, size, image.words()).next(),
+                Some(m.offset)
+            );
+        }
+    }
 }
@@ -1,167 +1,363 @@
 // SPDX-FileCopyrightText: © 2025 TTKB, LLC
 // SPDX-License-Identifier: BSD-3-CLAUSE
+use clap::ValueEnum;
 use serde::Deserialize;
 use serde_yaml::{self};
-use std::collections::HashMap;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
+use crate::address_space::AddressSpace;
 use crate::arch::mips;
+use crate::delta::{self, DeltaCheck};
+use crate::fingerprint::digest_for_relocated_range;
 use crate::fingerprint::Fingerprint;
-use crate::rk::RabinKarpMIPSHasher;
+use crate::image::NormalizedImage;
+use crate::input::MappedFile;
+use crate::jump_table;
+use crate::rk::{FingerprintSet, RabinKarpMIPSHasher};
+use crate::vram::{self, Confidence};
 use crate::SerializeToYAML;
 use crate::{
-    MIPSFamily, Options, RODataOffset, RODataSignature, RODataSignatureType, SegmentOffset,
-    SegmentSignature,
+    DataSignature, FunctionOffset, FunctionSignature, JumpTableOffset, MIPSFamily, Options,
+    RODataOffset, RODataSignature, RODataSignatureType, RelocationMask, SegmentOffset,
+    SegmentSignature, Signature,
 };
 
-// determine if the block specified by offset and size overlap with
-// addresses already in allocated_address_space
-pub fn address_space_is_used(
-    offset: usize,
-    size: usize,
-    allocated_address_space: &HashMap<usize, usize>,
-) -> bool {
-    let end = offset + size;
-    // O(n) lookup is not ideal, but fast enough for now
-    for (block_start, block_size) in allocated_address_space.iter() {
-        let block_end = *block_start + block_size;
-        if (offset >= *block_start && offset < block_end)
-            || (end > *block_start && end <= block_end)
-        {
-            return true;
-        }
-    }
-    return false;
+/// Finds the first occurrence of `fingerprint` in `instructions`, a slice
+/// of already normalized instructions. `len` is the number of instructions
+/// the fingerprint covers. The result is a byte offset.
+pub fn find<W: Write>(
+    fingerprint: Fingerprint,
+    len: usize,
+    instructions: &[u32],
+    options: &mut Options<W>,
+) -> Option<usize> {
+    let Fingerprint::V0(fp) = fingerprint;
+    let modulus = fp.modulus().unwrap_or(options.modulus);
+    let hasher = RabinKarpMIPSHasher::new_with_modulus(options.mips_family, modulus);
+
+    hasher
+        .rolling_hashes(instructions, len)
+        .find(|(_, hash)| *hash == fp.hash())
+        .map(|(offset, _)| offset)
 }
 
-fn find_only_jump_tables(
+// the offsets of words outside of the segment which point inside of it, as
+// jump table entries do. function starts are skipped, since those are
+// function pointers rather than jump targets
+fn jump_table_entries(
     segment_start: usize,
     segment_end: usize,
     vrom_start: usize,
     vrom_end: usize,
-    rodata_size: usize,
+    function_starts: &HashSet<usize>,
     mips_family: MIPSFamily,
     bytes: &[u8],
-) -> Option<RODataOffset> {
-    let mut found_segment_addr = false;
-    let mut last_found_jtable_addr = 0;
-    let mut range_start = 0;
+) -> Vec<usize> {
+    bytes
+        .chunks_exact(4)
+        .enumerate()
+        .map(|(i, word)| (i * 4, mips::read_word(word, mips_family) as usize))
+        .filter(|(i, _)| *i < vrom_start || *i >= vrom_end)
+        .filter(|(_, addr)| {
+            *addr > segment_start && *addr < segment_end && !function_starts.contains(addr)
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
 
-    for i in (0..bytes.len()).step_by(4) {
-        if i >= vrom_start && i < vrom_end {
+fn find_only_jump_tables(entries: &[usize], rodata_size: usize) -> Option<usize> {
+    let mut range_start = *entries.first()?;
+    for (i, entry) in entries.iter().enumerate() {
+        if entries.get(i + 1) == Some(&(entry + 4)) {
             continue;
         }
+        if entry + 4 - range_start == rodata_size {
+            return Some(range_start);
+        }
+        if let Some(next) = entries.get(i + 1) {
+            range_start = *next;
+        }
+    }
 
-        let addr = mips::read_word(&bytes[i..(i + 4)], mips_family) as usize;
+    None
+}
 
-        if addr > segment_start && addr < segment_end {
-            if !found_segment_addr {
-                found_segment_addr = true;
-                range_start = i;
-            }
-        } else if found_segment_addr {
-            found_segment_addr = false;
-            if (i - range_start) == rodata_size {
-                // println!("found rodata segment at 0x{:X}", range_start);
-                return Some(RODataOffset {
-                    offset: range_start,
-                    size: rodata_size,
-                });
-            }
+fn find_starts_and_ends_with_jump_table(entries: &[usize], rodata_size: usize) -> Option<usize> {
+    let last = (rodata_size & !3).checked_sub(4)?;
+    entries
+        .iter()
+        .copied()
+        .find(|entry| entries.binary_search(&(entry + last)).is_ok())
+}
+
+// the offsets of each contiguous run of jump table entries
+fn jump_table_runs(entries: &[usize]) -> Vec<Range<usize>> {
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    for &entry in entries {
+        match runs.last_mut() {
+            Some(run) if run.end == entry => run.end += 4,
+            _ => runs.push(entry..(entry + 4)),
         }
     }
+    runs
+}
 
-    None
+// RODATA which starts with a jump table and ends with other data starts at
+// a run of entries shorter than it. of several, the one closest to the
+// segment at `vrom_start` is used
+fn find_starts_with_jump_table(
+    entries: &[usize],
+    rodata_size: usize,
+    vrom_start: usize,
+    len: usize,
+) -> Option<usize> {
+    jump_table_runs(entries)
+        .into_iter()
+        .filter(|run| run.len() < (rodata_size & !3) && run.start + rodata_size <= len)
+        .map(|run| run.start)
+        .min_by_key(|offset| offset.abs_diff(vrom_start))
 }
 
+// like `find_starts_with_jump_table`, for RODATA which ends with a run of
+// entries
 fn find_ends_with_jump_table(
-    segment_start: usize,
-    segment_end: usize,
-    vrom_start: usize,
-    vrom_end: usize,
+    entries: &[usize],
     rodata_size: usize,
+    vrom_start: usize,
+) -> Option<usize> {
+    jump_table_runs(entries)
+        .into_iter()
+        .filter(|run| run.len() < (rodata_size & !3))
+        .filter_map(|run| run.end.checked_sub(rodata_size & !3))
+        .min_by_key(|offset| offset.abs_diff(vrom_start))
+}
+
+// RODATA without jump tables at either end is found by its contents, see
+// `find_rodata_hits`. of several copies outside of the segment, the one
+// closest to it is used
+fn find_unknown(hits: &[usize], size: usize, vrom_start: usize, vrom_end: usize) -> Option<usize> {
+    hits.iter()
+        .copied()
+        .filter(|offset| offset + size <= vrom_start || *offset >= vrom_end)
+        .min_by_key(|offset| offset.abs_diff(vrom_start))
+}
+
+// the functions outside of the segment which its matched functions call,
+// named after the functions called in the reference. a name whose calls
+// land in different places, or outside of the bin, is left out
+fn find_callees(
+    functions: &[FunctionSignature],
+    symbols: &HashMap<String, usize>,
+    vram_start: usize,
     mips_family: MIPSFamily,
     bytes: &[u8],
-) -> Option<RODataOffset> {
-    let mut found_segment_addr = false;
-    let mut last_found_jtable_addr = 0;
-    let mut last_offset = 0;
-
-    for i in (0..bytes.len()).step_by(4) {
-        if i >= vrom_start && i < vrom_end {
+) -> HashMap<String, usize> {
+    let mut callees: HashMap<String, Option<usize>> = HashMap::new();
+    for function in functions {
+        let Some(&offset) = symbols.get(&function.name) else {
             continue;
+        };
+
+        for call in function.calls.iter() {
+            if symbols.contains_key(&call.name) {
+                continue;
+            }
+            let call_offset = offset + call.offset;
+            let Some(word) = bytes.get(call_offset..(call_offset + 4)) else {
+                continue;
+            };
+            let instruction = mips::read_word(word, mips_family);
+            let target =
+                ((vram_start + call_offset) & !mips::TARGET_MASK) | mips::jump_target(instruction);
+            let callee = target
+                .checked_sub(vram_start)
+                .filter(|callee| *callee < bytes.len());
+
+            let entry = callees.entry(call.name.clone()).or_insert(callee);
+            if *entry != callee {
+                *entry = None;
+            }
         }
+    }
 
-        let addr = mips::read_word(&bytes[i..(i + 4)], mips_family) as usize;
+    callees
+        .into_iter()
+        .filter_map(|(name, offset)| Some((name, offset?)))
+        .collect()
+}
 
-        if addr > segment_start && addr < segment_end {
-            // println!("found rodata offset: 0x{:X} -> 0x{:X}", i, addr);
-            found_segment_addr = true;
-            last_found_jtable_addr = addr;
-            last_offset = i;
+// the jump tables of a segment's matched functions, found by following
+// their code. a table without a bounds check extends as far as its entries
+// point into its function
+fn find_jump_tables(
+    functions: &[FunctionSignature],
+    symbols: &HashMap<String, usize>,
+    vram_start: usize,
+    mips_family: MIPSFamily,
+    bytes: &[u8],
+) -> Vec<JumpTableOffset> {
+    let mut jump_tables = Vec::new();
+    for function in functions {
+        let Some(&offset) = symbols.get(&function.name) else {
+            continue;
+        };
+        let Some(code) = bytes.get(offset..(offset + function.size)) else {
+            continue;
+        };
+        let function_vram = (vram_start + offset)..(vram_start + offset + function.size);
+
+        for table in jump_table::find_jump_tables(code, mips_family) {
+            let Some(table_offset) = table.vram.checked_sub(vram_start) else {
+                continue;
+            };
+            let Some(table_bytes) = bytes.get(table_offset..) else {
+                continue;
+            };
+            let entries = table.entries.unwrap_or_else(|| {
+                table_bytes
+                    .chunks_exact(4)
+                    .take_while(|word| {
+                        function_vram.contains(&(mips::read_word(word, mips_family) as usize))
+                    })
+                    .count()
+            });
+            if entries == 0 || entries * 4 > table_bytes.len() {
+                continue;
+            }
+
+            jump_tables.push(JumpTableOffset {
+                function: function.name.clone(),
+                offset: table_offset,
+                size: entries * 4,
+            });
         }
     }
 
-    let rodata_offset = last_offset - rodata_size + 4;
+    jump_tables.sort_by_key(|jump_table| jump_table.offset);
+    jump_tables.dedup_by_key(|jump_table| jump_table.offset);
+    jump_tables
+}
 
-    if found_segment_addr && rodata_offset > 0 && rodata_offset < bytes.len() {
-        Some(RODataOffset {
-            offset: rodata_offset,
-            size: rodata_size,
-        })
-    } else {
-        None
+// the RODATA implied by a segment's jump tables, from where jump tables are
+// in the RODATA it was fingerprinted from. without a signature, the RODATA
+// spans the tables
+fn rodata_for_jump_tables(
+    rodata: Option<&RODataSignature>,
+    jump_tables: &[JumpTableOffset],
+) -> Option<(usize, usize)> {
+    let start = jump_tables.first()?.offset;
+    let end = jump_tables
+        .iter()
+        .map(|jump_table| jump_table.offset + jump_table.size)
+        .max()?;
+
+    let Some(rodata) = rodata else {
+        return Some((start, end - start));
+    };
+    match rodata.rodata_type {
+        RODataSignatureType::OnlyJumpTables
+        | RODataSignatureType::StartsAndEndsWithJumpTable
+        | RODataSignatureType::StartsWithJumpTable => Some((start, rodata.size)),
+        RODataSignatureType::EndsWithJumpTable => {
+            Some((end.checked_sub(rodata.size & !3)?, rodata.size))
+        }
+        RODataSignatureType::Unknown => None,
     }
 }
 
-// TODO: need size of each function as well
-fn find_rodata(
-    rodata: &Option<RODataSignature>,
+// locates the RODATA of a segment. jump tables found by following the
+// segment's code are used when there are any, otherwise the bin is searched
+// for words which look like jump table entries
+fn find_rodata<W: Write>(
+    candidate: &Candidate,
     vram_start: &Option<usize>,
-    segment_offset: usize,
-    segment_size: usize,
-    mips_family: MIPSFamily,
-    functions: &HashMap<String, usize>,
+    rodata_hits: &RODataHits,
     bytes: &[u8],
+    options: &Options<W>,
 ) -> Option<RODataOffset> {
-    let Some(ref rodata) = rodata else {
-        return None;
+    let segment = candidate.segment;
+    let jump_tables = match vram_start {
+        Some(vram_start) => find_jump_tables(
+            &segment.functions,
+            &candidate.symbols,
+            *vram_start,
+            options.mips_family,
+            bytes,
+        ),
+        None => Vec::new(),
     };
 
-    let Some(vram_start) = vram_start else {
-        return None;
-    };
+    let (offset, size) = rodata_for_jump_tables(segment.rodata.as_ref(), &jump_tables)
+        .or_else(|| {
+            let rodata = segment.rodata.as_ref()?;
+            let offset = search_rodata(rodata, candidate, vram_start, rodata_hits, bytes, options)?;
+            Some((offset, rodata.size))
+        })
+        .or_else(|| rodata_for_jump_tables(None, &jump_tables))?;
 
-    let segment_start = vram_start + segment_offset;
-    let segment_end = segment_start + segment_size;
+    Some(RODataOffset {
+        offset,
+        size,
+        jump_tables,
+    })
+}
 
-    // println!("looking for rodata in 0x{:X} to 0x{:X}", segment_start, segment_end);
+fn search_rodata<W: Write>(
+    rodata: &RODataSignature,
+    candidate: &Candidate,
+    vram_start: &Option<usize>,
+    rodata_hits: &RODataHits,
+    bytes: &[u8],
+    options: &Options<W>,
+) -> Option<usize> {
+    let mips_family = options.mips_family;
+    let vrom_start = candidate.offset;
+    let vrom_end = candidate.offset + candidate.size;
 
-    match rodata.rodataType {
-        RODataSignatureType::OnlyJumpTables => find_only_jump_tables(
-            segment_start,
-            segment_end,
-            segment_offset,
-            segment_offset + segment_size,
-            rodata.size,
-            mips_family,
-            bytes,
-        ),
-        RODataSignatureType::EndsWithJumpTable => find_ends_with_jump_table(
-            segment_start,
-            segment_end,
-            segment_offset,
-            segment_offset + segment_size,
-            rodata.size,
-            mips_family,
-            bytes,
-        ),
-        _ => None,
+    // unknown RODATA is found by its contents, and jump table entries can
+    // only be recognized knowing where the segment is loaded
+    if rodata.rodata_type == RODataSignatureType::Unknown {
+        let hits = rodata_hits.get(rodata)?;
+        return find_unknown(hits, rodata.size & !3, vrom_start, vrom_end);
+    }
+    let vram_start = (*vram_start)?;
+    let segment_start = vram_start + vrom_start;
+    let segment_end = vram_start + vrom_end;
+    let function_starts = candidate
+        .symbols
+        .values()
+        .map(|offset| vram_start + offset)
+        .collect::<HashSet<_>>();
+    let entries = jump_table_entries(
+        segment_start,
+        segment_end,
+        vrom_start,
+        vrom_end,
+        &function_starts,
+        mips_family,
+        bytes,
+    );
+
+    match rodata.rodata_type {
+        RODataSignatureType::OnlyJumpTables => find_only_jump_tables(&entries, rodata.size),
+        RODataSignatureType::StartsAndEndsWithJumpTable => {
+            find_starts_and_ends_with_jump_table(&entries, rodata.size)
+        }
+        RODataSignatureType::StartsWithJumpTable => {
+            find_starts_with_jump_table(&entries, rodata.size, vrom_start, bytes.len())
+        }
+        RODataSignatureType::EndsWithJumpTable => {
+            find_ends_with_jump_table(&entries, rodata.size, vrom_start)
+        }
+        RODataSignatureType::Unknown => None,
     }
 }
 
-fn best_name(names: &Vec<String>) -> Option<String> {
+fn best_name(names: &[String]) -> Option<String> {
     let mut pop: HashMap<String, usize> = HashMap::new();
     for name in names {
         *pop.entry(name.clone()).or_insert(0) += 1;
@@ -172,22 +368,878 @@ fn best_name(names: &Vec<String>) -> Option<String> {
     names.first().cloned()
 }
 
+// confirm a fingerprint hit at `offset` by comparing the digest of the
+// instructions found there. signatures with relocations are compared
+// exactly except for the relocated fields. signatures without a digest
+// are accepted.
+fn verify(
+    fingerprint: &Fingerprint,
+    digest: Option<u64>,
+    relocations: &Option<Vec<RelocationMask>>,
+    offset: usize,
+    image: &NormalizedImage,
+) -> bool {
+    let Some(digest) = digest else {
+        return true;
+    };
+
+    let Fingerprint::V0(fp) = fingerprint;
+    let size = fp.size() as usize;
+    let found = match relocations {
+        Some(relocations) => image.relocated_digest(offset, size, relocations),
+        None => image.digest(offset, size),
+    };
+    found == Some(digest)
+}
+
+// find each of the segment's functions, in order, starting at `offset`.
+//
+// without `max_padding` each function may be anywhere after the previous
+// one within the segment's size. with `max_padding`, each function must
+// follow the previous function's instructions with at most that many
+// zero words between them.
+//
+// returns `None` unless every function is found, otherwise the offset of
+// each function and the end of the last function.
+fn find_functions(
+    hasher: &RabinKarpMIPSHasher,
+    segment: &SegmentSignature,
+    offset: usize,
+    max_padding: Option<usize>,
+    image: &NormalizedImage,
+) -> Option<(HashMap<String, usize>, usize)> {
+    let mut map = HashMap::new();
+
+    let end = cmp::min(offset + segment.size, image.len());
+    let mut position = offset;
+
+    for function in segment.functions.iter() {
+        let Fingerprint::V0(fp) = function.fingerprint;
+        let fp_size = fp.size() as usize;
+
+        let window_end = match max_padding {
+            None => end,
+            Some(words) => cmp::min(position + words * 4 + fp_size, image.len()),
+        };
+        if position > window_end {
+            return None;
+        }
+
+        // only zero words normalize to zero
+        let window = image.window(position, window_end);
+        let function_offset = hasher
+            .find_all_words(fp.hash(), fp_size, window)
+            .filter(|&o| max_padding.is_none() || window[..o / 4].iter().all(|&w| w == 0))
+            .find(|&o| {
+                verify(
+                    &function.fingerprint,
+                    function.digest,
+                    &function.relocations,
+                    position + o,
+                    image,
+                )
+            })?;
+        map.insert(function.name.clone(), position + function_offset);
+
+        position = match max_padding {
+            None => cmp::min(position + function_offset + function.size, end),
+            Some(_) => position + function_offset + fp_size,
+        };
+    }
+
+    Some((map, position))
+}
+
+/// The level at which `scan` matches signatures.
+#[derive(ValueEnum, Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum Granularity {
+    /// Whole segments first, then stand-alone functions in the space left
+    #[default]
+    All,
+    /// Only whole segments
+    Segment,
+    /// Only stand-alone functions
+    Function,
+}
+
+/// Options which only apply to `scan`.
+#[derive(Clone, Debug)]
+pub struct ScanOptions {
+    pub granularity: Granularity,
+    /// The location the scanned binary would be loaded in VRAM.
+    pub vram_start: Option<usize>,
+    /// When set, segments which do not match as a whole are also reported
+    /// as partial matches when at least this fraction of their functions
+    /// are found in order.
+    pub min_coverage: Option<f64>,
+    /// When set, segments which do not match as a whole are also matched
+    /// as an ordered list of functions with up to this many zero words of
+    /// padding between them.
+    pub max_padding: Option<usize>,
+    /// The bin is searched this many bytes at a time so that only a
+    /// window of it needs to be normalized in memory.
+    pub chunk_size: usize,
+}
+
+impl ScanOptions {
+    pub const DEFAULT_CHUNK_SIZE: usize = 0x4000000;
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            granularity: Granularity::default(),
+            vram_start: None,
+            min_coverage: None,
+            max_padding: None,
+            chunk_size: Self::DEFAULT_CHUNK_SIZE,
+        }
+    }
+}
+
+// the most bytes any single match can span, which is how far chunks of the
+// bin must overlap
+fn match_extent(segments: &[&SegmentSignature], max_padding: Option<usize>) -> usize {
+    let padding = max_padding.unwrap_or(0) * 4;
+    segments
+        .iter()
+        .map(|segment| {
+            let Fingerprint::V0(fp) = segment.fingerprint;
+            let padded = segment
+                .functions
+                .iter()
+                .map(|function| {
+                    let Fingerprint::V0(fp) = function.fingerprint;
+                    cmp::max(function.size, fp.size() as usize) + padding
+                })
+                .sum::<usize>();
+            cmp::max(cmp::max(segment.size, fp.size() as usize), padded)
+        })
+        .max()
+        .unwrap_or(0)
+        .next_multiple_of(4)
+}
+
+// functions are identified by their fingerprint and digest
+type FunctionKey = (Fingerprint, Option<u64>);
+
+fn function_key(function: &FunctionSignature) -> FunctionKey {
+    (function.fingerprint, function.digest)
+}
+
+// every distinct function in the match files along with all of the names
+// it was given
+fn unique_functions<'a>(
+    segments: &[&'a SegmentSignature],
+) -> Vec<(&'a FunctionSignature, Vec<String>)> {
+    let mut functions: Vec<(&FunctionSignature, Vec<String>)> = Vec::new();
+    let mut index: HashMap<FunctionKey, usize> = HashMap::new();
+
+    for function in segments.iter().flat_map(|segment| segment.functions.iter()) {
+        let key = function_key(function);
+        match index.get(&key) {
+            Some(&i) => functions[i].1.push(function.name.clone()),
+            None => {
+                index.insert(key, functions.len());
+                functions.push((function, vec![function.name.clone()]));
+            }
+        }
+    }
+
+    functions
+}
+
+// the verified offsets, in ascending order, of every function in a single
+// pass over the bin
+fn find_function_hits(
+    functions: &[(&FunctionSignature, Vec<String>)],
+    hasher: &RabinKarpMIPSHasher,
+    image: &NormalizedImage,
+) -> HashMap<FunctionKey, Vec<usize>> {
+    let mut fingerprints = FingerprintSet::new();
+    for (id, (function, _)) in functions.iter().enumerate() {
+        let Fingerprint::V0(fp) = function.fingerprint;
+        fingerprints.insert(id, fp.hash(), fp.size() as usize);
+    }
+
+    let mut function_hits: HashMap<FunctionKey, Vec<usize>> = HashMap::new();
+    for hit in hasher.find_set(&fingerprints, image) {
+        let function = functions[hit.id].0;
+        if verify(
+            &function.fingerprint,
+            function.digest,
+            &function.relocations,
+            hit.offset,
+            image,
+        ) {
+            function_hits
+                .entry(function_key(function))
+                .or_default()
+                .push(hit.offset);
+        }
+    }
+
+    function_hits
+}
+
+// how much a candidate is trusted, by how it was found
+const FULL_CONFIDENCE: f64 = 1.0;
+const PADDED_CONFIDENCE: f64 = 0.9;
+const PARTIAL_CONFIDENCE: f64 = 0.75;
+// a whole match whose references to itself moved by different amounts
+const INCONSISTENT_CONFIDENCE: f64 = 0.5;
+
+// each matched function adds this many bytes worth of weight, so that a
+// segment with many functions outweighs one large function of equal size
+const FUNCTION_WEIGHT: f64 = 16.0;
+
+// a possible placement of a segment in the bin
+struct Candidate<'a> {
+    segment: &'a SegmentSignature,
+    offset: usize,
+    size: usize,
+    symbols: HashMap<String, usize>,
+    coverage: Option<f64>,
+    missing: Vec<String>,
+    delta: (DeltaCheck, Option<isize>),
+    weight: f64,
+}
+
+impl<'a> Candidate<'a> {
+    fn new(
+        segment: &'a SegmentSignature,
+        offset: usize,
+        size: usize,
+        symbols: HashMap<String, usize>,
+        confidence: f64,
+    ) -> Self {
+        let weight = confidence * (size as f64 + FUNCTION_WEIGHT * symbols.len() as f64);
+        Self {
+            segment,
+            offset,
+            size,
+            symbols,
+            coverage: None,
+            missing: Vec::new(),
+            delta: (DeltaCheck::Unchecked, None),
+            weight,
+        }
+    }
+
+    // move a candidate found in a chunk starting at `base`
+    fn rebase(mut self, base: usize) -> Self {
+        self.offset += base;
+        for offset in self.symbols.values_mut() {
+            *offset += base;
+        }
+        self
+    }
+}
+
+// every place the segment fingerprints match as a whole
+fn full_segment_candidates<'a>(
+    segments: &[&'a SegmentSignature],
+    hasher: &RabinKarpMIPSHasher,
+    image: &NormalizedImage,
+) -> Vec<Candidate<'a>> {
+    // every segment fingerprint is searched for in a single pass over the bin
+    let mut fingerprints = FingerprintSet::new();
+    for (id, segment) in segments.iter().enumerate() {
+        let Fingerprint::V0(fp) = segment.fingerprint;
+        fingerprints.insert(id, fp.hash(), fp.size() as usize);
+    }
+
+    let mut candidates = Vec::new();
+    for hit in hasher.find_set(&fingerprints, image) {
+        let segment = segments[hit.id];
+        if !verify(
+            &segment.fingerprint,
+            segment.digest,
+            &segment.relocations,
+            hit.offset,
+            image,
+        ) {
+            continue;
+        }
+
+        let Some((map, _)) = find_functions(hasher, segment, hit.offset, None, image) else {
+            continue;
+        };
+
+        let mut candidate = Candidate::new(segment, hit.offset, segment.size, map, FULL_CONFIDENCE);
+        let bytes = image.bytes();
+        let code = &bytes[hit.offset..cmp::min(hit.offset + segment.size, bytes.len())];
+        candidate.delta = delta::check_delta(&segment.references, code, image.family());
+        candidates.push(candidate);
+    }
+
+    candidates
+}
+
+// match segments as an ordered list of functions separated by no more than
+// `max_padding` zero words. this finds segments whose functions were laid
+// out with different alignment than the reference.
+fn padded_segment_candidates<'a>(
+    segments: &[&'a SegmentSignature],
+    function_hits: &HashMap<FunctionKey, Vec<usize>>,
+    max_padding: usize,
+    hasher: &RabinKarpMIPSHasher,
+    image: &NormalizedImage,
+) -> Vec<Candidate<'a>> {
+    let mut candidates = Vec::new();
+
+    for segment in segments {
+        let Some(first) = segment.functions.first() else {
+            continue;
+        };
+        let Some(anchors) = function_hits.get(&function_key(first)) else {
+            continue;
+        };
+
+        for &offset in anchors {
+            let Some((map, end)) =
+                find_functions(hasher, segment, offset, Some(max_padding), image)
+            else {
+                continue;
+            };
+
+            candidates.push(Candidate::new(
+                segment,
+                offset,
+                end - offset,
+                map,
+                PADDED_CONFIDENCE,
+            ));
+        }
+    }
+
+    candidates
+}
+
+// walk the segment's functions in order starting at `start`, taking the
+// earliest hit of each function which still fits in the segment. returns
+// the offset of each function, if found.
+fn partial_match(
+    segment: &SegmentSignature,
+    start: usize,
+    function_hits: &HashMap<FunctionKey, Vec<usize>>,
+) -> Vec<Option<usize>> {
+    let end = start + segment.size;
+    let mut position = start;
+
+    segment
+        .functions
+        .iter()
+        .map(|function| {
+            let Fingerprint::V0(fp) = function.fingerprint;
+            let fp_size = fp.size() as usize;
+            let hits = function_hits.get(&function_key(function))?;
+
+            let first = hits.partition_point(|&hit| hit < position);
+            let hit = *hits.get(first)?;
+            if hit + fp_size > end {
+                return None;
+            }
+
+            position = hit + cmp::max(function.size, fp_size);
+            Some(hit)
+        })
+        .collect()
+}
+
+// segments where only some of the functions were found in order. these
+// are typically built from a slightly edited copy of a shared file.
+fn partial_segment_candidates<'a>(
+    segments: &[&'a SegmentSignature],
+    function_hits: &HashMap<FunctionKey, Vec<usize>>,
+    min_coverage: f64,
+) -> Vec<Candidate<'a>> {
+    let mut candidates = Vec::new();
+
+    for segment in segments {
+        let total = segment.functions.len();
+        if total < 2 {
+            continue;
+        }
+
+        // every hit of any of the segment's functions could be where the
+        // partial match begins
+        let mut anchors = segment
+            .functions
+            .iter()
+            .filter_map(|function| function_hits.get(&function_key(function)))
+            .flatten()
+            .copied()
+            .collect::<Vec<usize>>();
+        anchors.sort();
+        anchors.dedup();
+
+        let mut seen = HashSet::new();
+        for anchor in anchors {
+            let found = partial_match(segment, anchor, function_hits);
+
+            let found_count = found.iter().flatten().count();
+            let coverage = found_count as f64 / total as f64;
+            if found_count < 2 || coverage < min_coverage {
+                continue;
+            }
+
+            let mut symbols = HashMap::new();
+            let mut missing = Vec::new();
+            let mut matched_size = 0;
+            let mut end = 0;
+            for (function, offset) in segment.functions.iter().zip(found.iter()) {
+                match offset {
+                    Some(offset) => {
+                        symbols.insert(function.name.clone(), *offset);
+                        matched_size += function.size;
+                        end = cmp::max(end, offset + function.size);
+                    }
+                    None => missing.push(function.name.clone()),
+                }
+            }
+
+            let offset = found.iter().flatten().copied().min().unwrap();
+            if !seen.insert((offset, end)) {
+                continue;
+            }
+
+            let mut candidate = Candidate::new(
+                segment,
+                offset,
+                end - offset,
+                symbols,
+                PARTIAL_CONFIDENCE * coverage,
+            );
+            // only the bytes of the functions found count towards the weight
+            candidate.weight *= (matched_size as f64 + FUNCTION_WEIGHT * found_count as f64)
+                / (candidate.size as f64 + FUNCTION_WEIGHT * found_count as f64);
+            candidate.coverage = Some(coverage);
+            candidate.missing = missing;
+            candidates.push(candidate);
+        }
+    }
+
+    candidates
+}
+
+// padded and partial matches at the same place as a whole match have the
+// same layout, so they share its relocation delta check. matches whose
+// references moved by different amounts are then ranked lower
+fn apply_delta_checks(candidates: &mut [Candidate]) {
+    let checked = candidates
+        .iter()
+        .filter(|candidate| candidate.delta.0 != DeltaCheck::Unchecked)
+        .map(|candidate| {
+            (
+                (candidate.segment.name.as_str(), candidate.offset),
+                candidate.delta,
+            )
+        })
+        .collect::<HashMap<_, _>>();
+
+    for candidate in candidates.iter_mut() {
+        if candidate.size == candidate.segment.size {
+            if let Some(delta) = checked.get(&(candidate.segment.name.as_str(), candidate.offset)) {
+                candidate.delta = *delta;
+            }
+        }
+        if candidate.delta.0 == DeltaCheck::Inconsistent {
+            candidate.weight *= INCONSISTENT_CONFIDENCE;
+        }
+    }
+}
+
+/// Selects the set of non-overlapping intervals with the greatest total
+/// weight (weighted interval scheduling). Each interval is given as
+/// `(start, end, weight)` with an exclusive end. The indices of the chosen
+/// intervals are returned in ascending order of their end.
+pub fn schedule(intervals: &[(usize, usize, f64)]) -> Vec<usize> {
+    let mut order = (0..intervals.len()).collect::<Vec<usize>>();
+    order.sort_by_key(|&i| (intervals[i].1, intervals[i].0));
+
+    let ends = order.iter().map(|&i| intervals[i].1).collect::<Vec<_>>();
+
+    // best[j] is the best total weight using only the first j intervals
+    let mut best = vec![0.0; order.len() + 1];
+    for (j, &i) in order.iter().enumerate() {
+        let (start, _, weight) = intervals[i];
+        // number of intervals which end at or before this one starts
+        let compatible = ends[..j].partition_point(|&end| end <= start);
+        best[j + 1] = f64::max(best[j], weight + best[compatible]);
+    }
+
+    let mut chosen = Vec::new();
+    let mut j = order.len();
+    while j > 0 {
+        let i = order[j - 1];
+        let (start, _, weight) = intervals[i];
+        let compatible = ends[..j - 1].partition_point(|&end| end <= start);
+        if weight + best[compatible] >= best[j - 1] {
+            chosen.push(i);
+            j = compatible;
+        } else {
+            j -= 1;
+        }
+    }
+
+    chosen.reverse();
+    chosen
+}
+
+// the load address of the bin, inferred from the code of the chosen
+// candidates and noted as a comment ahead of the matches. a guess with low
+// confidence is reported, but not used
+fn infer_vram_start<W: Write>(
+    candidates: &[Option<Candidate>],
+    bytes: &[u8],
+    options: &mut Options<W>,
+) -> Option<usize> {
+    let segments = candidates
+        .iter()
+        .flatten()
+        .map(|candidate| {
+            candidate
+                .segment
+                .functions
+                .iter()
+                .filter_map(|function| {
+                    let offset = *candidate.symbols.get(&function.name)?;
+                    Some(offset..(offset + function.size))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let inferred = vram::infer_vram_start(&segments, bytes, options.mips_family)?;
+    writeln!(
+        options.writer,
+        "# vram_start: 0x{:X} (confidence: {}, references: {})",
+        inferred.vram_start,
+        serde_yaml::to_string(&inferred.confidence).unwrap().trim(),
+        inferred.references
+    )
+    .expect("vram start serialization");
+
+    (inferred.confidence > Confidence::Low).then_some(inferred.vram_start)
+}
+
+// choose the best non-overlapping candidates and report them in the order
+// they appear in the bin. returns the load address of the bin, if it was
+// given or could be inferred
+fn place_segments<W: Write>(
+    candidates: Vec<Candidate>,
+    name_map: &HashMap<Fingerprint, Vec<String>>,
+    rodata_hits: &RODataHits,
+    bytes: &[u8],
+    allocated_address_space: &mut AddressSpace<String>,
+    scan_options: &ScanOptions,
+    options: &mut Options<W>,
+) -> Option<usize> {
+    let intervals = candidates
+        .iter()
+        .map(|c| (c.offset, c.offset + c.size, c.weight))
+        .collect::<Vec<_>>();
+    let chosen = schedule(&intervals);
+
+    let mut candidates = candidates.into_iter().map(Some).collect::<Vec<_>>();
+    let mut chosen_candidates = Vec::with_capacity(chosen.len());
+    for i in chosen {
+        chosen_candidates.push(candidates[i].take());
+    }
+    let vram_start = scan_options
+        .vram_start
+        .or_else(|| infer_vram_start(&chosen_candidates, bytes, options));
+
+    for candidate in chosen_candidates {
+        let candidate = candidate.expect("candidate is only chosen once");
+        let segment = candidate.segment;
+
+        let empty_vec = &Vec::<String>::new();
+        let names = name_map.get(&segment.fingerprint).unwrap_or(empty_vec);
+        let name = best_name(names).unwrap_or(segment.name.clone());
+
+        allocated_address_space.insert(candidate.offset, candidate.size, name.clone());
+
+        // a partial match is not expected to have the same RODATA
+        let rodata_match = match candidate.coverage {
+            Some(_) => None,
+            None => find_rodata(&candidate, &vram_start, rodata_hits, bytes, options),
+        };
+
+        let callees = vram_start
+            .map(|vram_start| {
+                find_callees(
+                    &segment.functions,
+                    &candidate.symbols,
+                    vram_start,
+                    segment.family,
+                    bytes,
+                )
+            })
+            .unwrap_or_default();
+
+        let so = SegmentOffset {
+            name,
+            section: None,
+            offset: candidate.offset,
+            size: candidate.size,
+            rodata: rodata_match,
+            symbols: candidate.symbols,
+            coverage: candidate.coverage,
+            missing: candidate.missing,
+            delta_check: Some(candidate.delta.0),
+            vram_delta: candidate.delta.1,
+            callees,
+        };
+
+        writeln!(options.writer, "---").expect("Write ocument separator");
+        so.serialize_to_yaml(&mut options.writer);
+    }
+
+    vram_start
+}
+
+// the words of the bin as they are, which data and RODATA are compared
+// against rather than normalized instructions
+fn data_words(image: &NormalizedImage) -> Vec<u32> {
+    image
+        .bytes()
+        .chunks_exact(4)
+        .map(|word| mips::read_word(word, image.family()))
+        .collect()
+}
+
+// the verified offsets of every data signature in a single pass over the
+// bin, keyed by the index of the signature
+fn find_data_hits(
+    data: &[DataSignature],
+    hasher: &RabinKarpMIPSHasher,
+    image: &NormalizedImage,
+    words: &[u32],
+) -> HashMap<usize, Vec<usize>> {
+    let mut fingerprints = FingerprintSet::new();
+    for (id, signature) in data.iter().enumerate() {
+        let Fingerprint::V0(fp) = signature.fingerprint;
+        fingerprints.insert(id, fp.hash(), fp.size() as usize);
+    }
+
+    let bytes = image.bytes();
+    let mut data_hits: HashMap<usize, Vec<usize>> = HashMap::new();
+    for hit in hasher.find_set_words(&fingerprints, words) {
+        let signature = &data[hit.id];
+        let Some(offset) = hit.offset.checked_sub(signature.anchor) else {
+            continue;
+        };
+        let Some(found) = bytes.get(offset..(offset + signature.size)) else {
+            continue;
+        };
+        if digest_for_relocated_range(found, &signature.relocations, image.family())
+            == signature.digest
+        {
+            data_hits.entry(hit.id).or_default().push(offset);
+        }
+    }
+
+    data_hits
+}
+
+// the verified offsets of unknown RODATA, by signature. identical RODATA
+// of different segments shares its offsets
+type RODataHits<'a> = HashMap<&'a RODataSignature, Vec<usize>>;
+
+// the verified offsets of the unknown RODATA of every segment in a single
+// pass over the bin. the words which held pointers in the reference, jump
+// table entries included, are masked
+fn find_rodata_hits<'a>(
+    rodata: &[&'a RODataSignature],
+    hasher: &RabinKarpMIPSHasher,
+    image: &NormalizedImage,
+    words: &[u32],
+) -> RODataHits<'a> {
+    let mut fingerprints = FingerprintSet::new();
+    for (id, signature) in rodata.iter().enumerate() {
+        if let Some(Fingerprint::V0(fp)) = signature.fingerprint {
+            fingerprints.insert(id, fp.hash(), fp.size() as usize);
+        }
+    }
+
+    let bytes = image.bytes();
+    let mut rodata_hits: RODataHits = HashMap::new();
+    for hit in hasher.find_set_words(&fingerprints, words) {
+        let signature = rodata[hit.id];
+        let Some(offset) = hit.offset.checked_sub(signature.anchor) else {
+            continue;
+        };
+        let Some(found) = bytes.get(offset..(offset + (signature.size & !3))) else {
+            continue;
+        };
+        if signature.digest.is_none_or(|digest| {
+            digest_for_relocated_range(found, &signature.relocations, image.family()) == digest
+        }) {
+            rodata_hits.entry(signature).or_default().push(offset);
+        }
+    }
+
+    rodata_hits
+}
+
+// whether every pointer of the data at `offset` points into the bin, when
+// it is loaded at `vram_start`
+fn points_into_bin(
+    signature: &DataSignature,
+    offset: usize,
+    vram_start: usize,
+    bytes: &[u8],
+) -> bool {
+    let vram = vram_start..(vram_start + bytes.len());
+    signature
+        .relocations
+        .iter()
+        .filter(|relocation| relocation.mask == 0xFFFFFFFF)
+        .all(|relocation| {
+            let start = offset + relocation.offset;
+            bytes.get(start..(start + 4)).is_some_and(|word| {
+                vram.contains(&(mips::read_word(word, signature.family) as usize))
+            })
+        })
+}
+
+// report data which is found exactly once, outside of space claimed by
+// code, in the order it appears in the bin. when data is found more than
+// once and the load address is known, the one copy whose pointers all
+// point into the bin is used
+fn place_data<W: Write>(
+    data: &[DataSignature],
+    data_hits: &HashMap<usize, Vec<usize>>,
+    vram_start: Option<usize>,
+    bytes: &[u8],
+    allocated_address_space: &mut AddressSpace<String>,
+    options: &mut Options<W>,
+) {
+    let mut found = data_hits
+        .iter()
+        .filter_map(|(&id, hits)| {
+            let signature = &data[id];
+            let hit = match hits.as_slice() {
+                [offset] => *offset,
+                _ => {
+                    let vram_start = vram_start?;
+                    let inside = hits
+                        .iter()
+                        .filter(|&&offset| points_into_bin(signature, offset, vram_start, bytes))
+                        .collect::<Vec<_>>();
+                    match inside.as_slice() {
+                        [offset] => **offset,
+                        _ => return None,
+                    }
+                }
+            };
+            Some((signature, hit))
+        })
+        .collect::<Vec<_>>();
+    // larger data is less likely to be coincidental, place it first
+    found.sort_by_key(|(signature, offset)| (cmp::Reverse(signature.size), *offset));
+
+    let mut data_offsets = Vec::new();
+    for (signature, offset) in found {
+        if !allocated_address_space.insert(offset, signature.size, signature.name.clone()) {
+            continue;
+        }
+
+        data_offsets.push(SegmentOffset {
+            name: signature.name.clone(),
+            section: Some(signature.section.clone()),
+            offset,
+            size: signature.size,
+            rodata: None,
+            symbols: signature
+                .symbols
+                .iter()
+                .map(|(name, symbol)| (name.clone(), offset + symbol))
+                .collect(),
+            coverage: None,
+            missing: Vec::new(),
+            delta_check: None,
+            vram_delta: None,
+            callees: HashMap::new(),
+        });
+    }
+
+    data_offsets.sort_by_key(|so| so.offset);
+    for so in data_offsets {
+        writeln!(options.writer, "---").expect("Write ocument separator");
+        so.serialize_to_yaml(&mut options.writer);
+    }
+}
+
+// search for every function on its own. only functions which are found
+// exactly once, and outside of space claimed by segments, are reported.
+fn scan_functions<W: Write>(
+    functions: &[(&FunctionSignature, Vec<String>)],
+    function_hits: &HashMap<FunctionKey, Vec<usize>>,
+    allocated_address_space: &mut AddressSpace<String>,
+    options: &mut Options<W>,
+) {
+    // larger functions are less likely to be coincidental, place them first
+    let mut found = functions
+        .iter()
+        .filter_map(|(function, names)| {
+            let hits = function_hits.get(&function_key(function))?;
+            if hits.len() == 1 {
+                Some((*function, names, hits[0]))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    found.sort_by_key(|(function, _, offset)| (cmp::Reverse(function.size), *offset));
+
+    let mut function_offsets = Vec::new();
+    for (function, names, offset) in found {
+        let name = best_name(names).unwrap_or(function.name.clone());
+        if !allocated_address_space.insert(offset, function.size, name.clone()) {
+            continue;
+        }
+
+        function_offsets.push(FunctionOffset {
+            name,
+            offset,
+            size: function.size,
+        });
+    }
+
+    function_offsets.sort_by_key(|fo| fo.offset);
+    for fo in function_offsets {
+        writeln!(options.writer, "---").expect("Write ocument separator");
+        fo.serialize_to_yaml(&mut options.writer);
+    }
+}
+
 pub fn scan<W: Write>(
     match_files: &Vec<PathBuf>,
-    bin_file: &PathBuf,
-    vram_start: Option<usize>,
+    bin_file: &Path,
+    scan_options: &ScanOptions,
     options: &mut Options<W>,
 ) {
     let mut segment_map: HashMap<SegmentSignature, usize> = HashMap::new();
     let mut name_map: HashMap<Fingerprint, Vec<String>> = HashMap::new();
+    let mut data = Vec::new();
     for match_file in match_files {
         let f = std::fs::File::open(match_file).unwrap();
         for document in serde_yaml::Deserializer::from_reader(io::BufReader::new(f)) {
-            let segment = SegmentSignature::deserialize(document).unwrap();
+            let segment = match Signature::deserialize(document).unwrap() {
+                Signature::Segment(segment) => segment,
+                Signature::Data(signature) => {
+                    options.mips_family = signature.family;
+                    data.push(signature);
+                    continue;
+                }
+            };
             // TODO: this should only be set once, and it should be checked for consistency
             options.mips_family = segment.family;
 
-            let entry = name_map.entry(segment.fingerprint).or_insert(Vec::new());
+            let entry = name_map.entry(segment.fingerprint).or_default();
             entry.push(segment.name.clone());
             *segment_map.entry(segment).or_insert(0) += 1;
         }
@@ -212,81 +1264,192 @@ pub fn scan<W: Write>(
         .map(|(segment, _)| *segment)
         .collect::<Vec<&SegmentSignature>>();
 
-    // use the most popular name for each segment
-
-    let mut allocated_address_space: HashMap<usize, usize> = HashMap::new();
+    let mut allocated_address_space = AddressSpace::new();
 
-    let bytes = std::fs::read(bin_file).expect("Could not read bin file");
+    let bytes = MappedFile::open(bin_file).expect("Could not read bin file");
 
     let hasher = RabinKarpMIPSHasher::new_with_modulus(options.mips_family, options.modulus);
+    let functions = unique_functions(&sorted_segments);
+    let unknown_rodata = sorted_segments
+        .iter()
+        .filter_map(|segment| segment.rodata.as_ref())
+        .filter(|rodata| rodata.rodata_type == RODataSignatureType::Unknown)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
 
-    for segment in sorted_segments {
-        let Fingerprint::V0(fp) = segment.fingerprint;
-        let fp_hash = fp.hash();
-        let fp_size = fp.size() as usize;
-
-        // try to find the entire object, first
-        let offset = hasher.find(fp_hash, fp_size, &bytes);
-
-        let Some(offset) = offset else {
-            continue;
-        };
-
-        // if this address space is already occupied, ignore
-        if address_space_is_used(offset, segment.size, &allocated_address_space) {
-            // println!(
-            //     "found used address space: {} ({}) {:?}",
-            //     offset, segment.size, segment
-            // );
-            continue;
-        }
+    // the bin is searched a chunk at a time. each chunk is extended by
+    // enough overlap that any match starting within it is seen whole.
+    // matches starting in the overlap belong to the next chunk.
+    let overlap = cmp::max(
+        match_extent(&sorted_segments, scan_options.max_padding),
+        data.iter()
+            .map(|signature| signature.size)
+            .chain(unknown_rodata.iter().map(|rodata| rodata.size))
+            .max()
+            .unwrap_or(0)
+            .next_multiple_of(4),
+    );
+    let chunk_size = cmp::max(scan_options.chunk_size / 4, 1) * 4;
 
-        allocated_address_space.insert(offset, segment.size);
+    let mut function_hits: HashMap<FunctionKey, Vec<usize>> = HashMap::new();
+    let mut data_hits: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut rodata_hits: RODataHits = HashMap::new();
+    let mut candidates = Vec::new();
+    let mut base = 0;
+    while base < bytes.len() {
+        let end = cmp::min(base + chunk_size + overlap, bytes.len());
+        let owned = cmp::min(chunk_size, bytes.len() - base);
+        let image = NormalizedImage::new(&bytes[base..end], options.mips_family);
 
-        let mut map = HashMap::new();
+        let chunk_hits = find_function_hits(&functions, &hasher, &image);
 
-        let mut position = offset;
-        let function_len = segment.functions.len();
+        if scan_options.granularity != Granularity::Function {
+            // gather every way each segment could be placed, then choose
+            // the best combination of them rather than claiming space
+            // greedily
+            let mut chunk_candidates = full_segment_candidates(&sorted_segments, &hasher, &image);
+            if let Some(max_padding) = scan_options.max_padding {
+                chunk_candidates.extend(padded_segment_candidates(
+                    &sorted_segments,
+                    &chunk_hits,
+                    max_padding,
+                    &hasher,
+                    &image,
+                ));
+            }
+            if let Some(min_coverage) = scan_options.min_coverage {
+                chunk_candidates.extend(partial_segment_candidates(
+                    &sorted_segments,
+                    &chunk_hits,
+                    min_coverage,
+                ));
+            }
+            apply_delta_checks(&mut chunk_candidates);
 
-        for function in segment.functions.iter() {
-            let Fingerprint::V0(fp) = function.fingerprint;
-            let fp_hash = fp.hash();
-            let fp_size = fp.size() as usize;
+            candidates.extend(
+                chunk_candidates
+                    .into_iter()
+                    .filter(|candidate| candidate.offset < owned)
+                    .map(|candidate| candidate.rebase(base)),
+            );
+        }
 
-            let function_offset =
-                hasher.find(fp_hash, fp_size, &bytes[position..(offset + segment.size)]);
-            if let Some(function_offset) = function_offset {
-                map.insert(function.name.clone(), position + function_offset);
-                position = function_offset + function.size;
+        if scan_options.granularity != Granularity::Function {
+            let words = data_words(&image);
+            for (id, hits) in find_data_hits(&data, &hasher, &image, &words) {
+                data_hits.entry(id).or_default().extend(
+                    hits.into_iter()
+                        .filter(|&offset| offset < owned)
+                        .map(|offset| offset + base),
+                );
+            }
+            for (rodata, hits) in find_rodata_hits(&unknown_rodata, &hasher, &image, &words) {
+                rodata_hits.entry(rodata).or_default().extend(
+                    hits.into_iter()
+                        .filter(|&offset| offset < owned)
+                        .map(|offset| offset + base),
+                );
             }
         }
 
-        if function_len != map.len() {
-            continue;
+        for (key, hits) in chunk_hits {
+            function_hits.entry(key).or_default().extend(
+                hits.into_iter()
+                    .filter(|&offset| offset < owned)
+                    .map(|offset| offset + base),
+            );
         }
 
-        let empty_vec = &Vec::<String>::new();
-        let names = name_map.get(&segment.fingerprint).unwrap_or(&empty_vec);
+        base += owned;
+    }
 
-        let rodata_match = find_rodata(
-            &segment.rodata,
-            &vram_start,
-            offset,
-            segment.size,
-            options.mips_family,
-            &map,
+    if scan_options.granularity != Granularity::Function {
+        let vram_start = place_segments(
+            candidates,
+            &name_map,
+            &rodata_hits,
+            &bytes,
+            &mut allocated_address_space,
+            scan_options,
+            options,
+        );
+        place_data(
+            &data,
+            &data_hits,
+            vram_start,
             &bytes,
+            &mut allocated_address_space,
+            options,
         );
+    }
 
-        let so = SegmentOffset {
-            name: best_name(names).unwrap_or(segment.name.clone()),
-            offset,
-            size: segment.size,
-            rodata: rodata_match,
-            symbols: map,
-        };
+    if scan_options.granularity != Granularity::Segment {
+        scan_functions(
+            &functions,
+            &function_hits,
+            &mut allocated_address_space,
+            options,
+        );
+    }
+}
 
-        writeln!(options.writer, "---").expect("Write ocument separator");
-        so.serialize_to_yaml(&mut options.writer);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_jump_tables_by_entries() {
+        // a stray pointer, then a table of three entries and another of two
+        let entries = [0x10, 0x40, 0x44, 0x48, 0x80, 0x84];
+        assert_eq!(
+            jump_table_runs(&entries),
+            vec![0x10..0x14, 0x40..0x4C, 0x80..0x88]
+        );
+
+        // the table nearest the segment which is shorter than the RODATA,
+        // and leaves room for the rest of it in the bin
+        assert_eq!(
+            find_starts_with_jump_table(&entries, 0x10, 0x100, 0x100),
+            Some(0x80)
+        );
+        assert_eq!(
+            find_starts_with_jump_table(&entries, 0x10, 0x0, 0x100),
+            Some(0x10)
+        );
+        assert_eq!(
+            find_starts_with_jump_table(&entries, 0x10, 0x100, 0x88),
+            Some(0x40)
+        );
+        assert_eq!(
+            find_starts_with_jump_table(&entries, 0xC, 0x40, 0x100),
+            Some(0x10)
+        );
+        assert_eq!(
+            find_starts_with_jump_table(&[0x40, 0x44], 0x8, 0x100, 0x100),
+            None
+        );
+
+        assert_eq!(find_ends_with_jump_table(&entries, 0x10, 0x100), Some(0x78));
+        assert_eq!(find_ends_with_jump_table(&entries, 0x10, 0x0), Some(0x4));
+        assert_eq!(find_ends_with_jump_table(&[0x40, 0x44], 0x8, 0x100), None);
+    }
+
+    #[test]
+    fn test_schedule() {
+        assert_eq!(schedule(&[]), Vec::<usize>::new());
+
+        // one large match overlapping two smaller ones which together
+        // outweigh it
+        let intervals = [(0, 100, 50.0), (0, 40, 30.0), (40, 100, 30.0)];
+        assert_eq!(schedule(&intervals), vec![1, 2]);
+
+        // unless it is heavy enough
+        let intervals = [(0, 100, 70.0), (0, 40, 30.0), (40, 100, 30.0)];
+        assert_eq!(schedule(&intervals), vec![0]);
+
+        // containment is an overlap, touching is not
+        let intervals = [(10, 20, 5.0), (0, 40, 4.0), (20, 30, 1.0), (40, 50, 1.0)];
+        assert_eq!(schedule(&intervals), vec![0, 2, 3]);
     }
 }
@@ -0,0 +1,386 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+use regex::Regex;
+use serde_yaml::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::map::{FunctionEntry, ObjectMap, SegmentInfo};
+use crate::MIPSFamily;
+
+// subsegment types which contain code
+static CODE_TYPES: [&str; 4] = ["c", "cpp", "asm", "hasm"];
+
+/// A symbol from a splat `symbol_addrs.txt` file.
+#[derive(Debug, PartialEq)]
+pub struct SymbolAddr {
+    pub name: String,
+    pub vram: usize,
+    /// The `type:` attribute, e.g. `func` or `data`.
+    pub symbol_type: Option<String>,
+    pub size: Option<usize>,
+    pub rom: Option<usize>,
+}
+
+impl SymbolAddr {
+    // symbols without a type in code are assumed to be functions
+    fn is_function(&self) -> bool {
+        self.symbol_type.as_deref().is_none_or(|t| t == "func")
+    }
+}
+
+// a number which may be hex (`0x...`) or decimal
+fn parse_number(s: &str) -> Option<usize> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => s.parse::<usize>().ok(),
+    }
+}
+
+fn value_to_number(value: &Value) -> Option<usize> {
+    match value {
+        Value::Number(n) => n.as_u64().map(|n| n as usize),
+        Value::String(s) => parse_number(s),
+        _ => None,
+    }
+}
+
+/// Parses the contents of a splat `symbol_addrs.txt` file. Lines have the
+/// form `name = 0x80010000; // type:func size:0x10 rom:0x1000`, where
+/// the attributes are optional.
+pub fn parse_symbol_addrs(text: &str) -> Vec<SymbolAddr> {
+    let line_re = Regex::new(r"^\s*([^\s=/]+)\s*=\s*(0[xX][0-9A-Fa-f]+|\d+)\s*;(.*)$").unwrap();
+
+    text.lines()
+        .filter_map(|line| {
+            let captures = line_re.captures(line)?;
+            let mut symbol = SymbolAddr {
+                name: captures[1].to_string(),
+                vram: parse_number(&captures[2])?,
+                symbol_type: None,
+                size: None,
+                rom: None,
+            };
+
+            let attributes = captures[3]
+                .split_once("//")
+                .map(|(_, comment)| comment)
+                .unwrap_or("");
+            for attribute in attributes.split_whitespace() {
+                match attribute.split_once(':') {
+                    Some(("type", t)) => symbol.symbol_type = Some(t.to_string()),
+                    Some(("size", size)) => symbol.size = parse_number(size),
+                    Some(("rom", rom)) => symbol.rom = parse_number(rom),
+                    _ => (),
+                }
+            }
+
+            Some(symbol)
+        })
+        .collect()
+}
+
+// a splat subsegment, either `[start, type, name]` or a map
+struct Subsegment {
+    start: Option<usize>,
+    subsegment_type: String,
+    name: Option<String>,
+}
+
+fn read_subsegment(value: &Value) -> Option<Subsegment> {
+    match value {
+        Value::Sequence(fields) => Some(Subsegment {
+            start: fields.first().and_then(value_to_number),
+            subsegment_type: fields.get(1)?.as_str()?.to_string(),
+            name: fields.get(2).and_then(|n| n.as_str()).map(str::to_string),
+        }),
+        Value::Mapping(_) => Some(Subsegment {
+            start: value.get("start").and_then(value_to_number),
+            subsegment_type: value.get("type")?.as_str()?.to_string(),
+            name: value
+                .get("name")
+                .and_then(|n| n.as_str())
+                .map(str::to_string),
+        }),
+        _ => None,
+    }
+}
+
+// the rom start of a top-level segment, either `[start, ...]` or a map
+fn segment_start(value: &Value) -> Option<usize> {
+    match value {
+        Value::Sequence(fields) => fields.first().and_then(value_to_number),
+        Value::Mapping(_) => value.get("start").and_then(value_to_number),
+        _ => None,
+    }
+}
+
+fn family_for_platform(platform: &str) -> Option<MIPSFamily> {
+    match platform {
+        "psx" => Some(MIPSFamily::R3000GTE),
+        "n64" => Some(MIPSFamily::R4000),
+        "ps2" => Some(MIPSFamily::R5900),
+        "psp" => Some(MIPSFamily::R4000Allegrex),
+        _ => None,
+    }
+}
+
+/// The segments of a splat config which contain code.
+pub struct SplatSegments {
+    /// The MIPS family of the config's `platform`, if it is known.
+    pub family: Option<MIPSFamily>,
+    /// Each code subsegment, with `offset` and function offsets into the
+    /// original binary and a `vrom` of 0.
+    pub segments: Vec<ObjectMap>,
+}
+
+/// Reads code subsegments from a splat config, and the functions in them
+/// from `symbol_addrs`.
+///
+/// A subsegment ends where the next subsegment (or top-level segment)
+/// starts. Functions are placed by their `rom:` attribute when they have
+/// one, otherwise by address. A function without a `size:` extends to the
+/// next symbol or the end of its subsegment. Subsegments which start
+/// before their segment, or after the subsegment which follows them, are
+/// skipped.
+pub fn read_segments(
+    config: &str,
+    symbol_addrs: &[SymbolAddr],
+) -> Result<SplatSegments, serde_yaml::Error> {
+    let config: Value = serde_yaml::from_str(config)?;
+
+    let family = config
+        .get("options")
+        .and_then(|options| options.get("platform"))
+        .and_then(|platform| platform.as_str())
+        .and_then(family_for_platform);
+
+    let top_level = config
+        .get("segments")
+        .and_then(|segments| segments.as_sequence())
+        .cloned()
+        .unwrap_or_default();
+
+    let mut segments = Vec::new();
+    for (i, segment) in top_level.iter().enumerate() {
+        let (Some(start), Some(subsegments)) = (
+            segment_start(segment),
+            segment.get("subsegments").and_then(|s| s.as_sequence()),
+        ) else {
+            continue;
+        };
+        let vram = segment
+            .get("vram")
+            .and_then(value_to_number)
+            .unwrap_or(start);
+        let segment_end = top_level[(i + 1)..].iter().find_map(segment_start);
+
+        // only subsegments with a rom start delimit the binary
+        let subsegments = subsegments
+            .iter()
+            .filter_map(read_subsegment)
+            .filter_map(|s| Some((s.start?, s)))
+            .collect::<Vec<_>>();
+
+        // rodata, keyed by file name
+        let mut rodata: HashMap<String, SegmentInfo> = HashMap::new();
+        for (j, (sub_start, subsegment)) in subsegments.iter().enumerate() {
+            let Some(sub_end) = subsegments
+                .get(j + 1)
+                .map(|(next, _)| *next)
+                .or(segment_end)
+            else {
+                continue;
+            };
+            let Some(ref name) = subsegment.name else {
+                continue;
+            };
+            let (Some(offset), Some(size)) = (
+                sub_start.checked_sub(start),
+                sub_end.checked_sub(*sub_start),
+            ) else {
+                continue;
+            };
+            if subsegment.subsegment_type.trim_start_matches('.') == "rodata" {
+                rodata.insert(
+                    name.clone(),
+                    SegmentInfo {
+                        vram: vram + offset,
+                        vrom: *sub_start,
+                        size,
+                    },
+                );
+            }
+        }
+
+        for (j, (sub_start, subsegment)) in subsegments.iter().enumerate() {
+            if !CODE_TYPES.contains(&subsegment.subsegment_type.as_str()) {
+                continue;
+            }
+            let Some(sub_end) = subsegments
+                .get(j + 1)
+                .map(|(next, _)| *next)
+                .or(segment_end)
+            else {
+                continue;
+            };
+
+            let sub_start = *sub_start;
+            let (Some(offset), Some(size)) =
+                (sub_start.checked_sub(start), sub_end.checked_sub(sub_start))
+            else {
+                continue;
+            };
+            let sub_vram = vram + offset;
+            let sub_vram_end = sub_vram + size;
+            let name = subsegment
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{:X}", sub_start));
+
+            // every symbol in the subsegment, to bound functions without sizes
+            let mut symbols = symbol_addrs
+                .iter()
+                .filter_map(|symbol| {
+                    let rom = match symbol.rom {
+                        Some(rom) => rom,
+                        None if symbol.vram >= sub_vram && symbol.vram < sub_vram_end => {
+                            symbol.vram - sub_vram + sub_start
+                        }
+                        None => return None,
+                    };
+                    if rom < sub_start || rom >= sub_end {
+                        return None;
+                    }
+                    Some((rom, symbol))
+                })
+                .collect::<Vec<_>>();
+            symbols.sort_by_key(|(rom, _)| *rom);
+
+            let text_symbols = symbols
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, symbol))| symbol.is_function())
+                .map(|(k, (rom, symbol))| {
+                    let next = symbols[(k + 1)..]
+                        .iter()
+                        .map(|(next, _)| *next)
+                        .find(|next| next > rom)
+                        .unwrap_or(sub_end);
+                    FunctionEntry {
+                        name: symbol.name.clone(),
+                        offset: *rom,
+                        vram: sub_vram + rom - sub_start,
+                        size: symbol.size.unwrap_or(next - rom),
+                        size_inferred: symbol.size.is_none(),
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            segments.push(ObjectMap {
+                object: format!("{}.o", name),
+                offset: sub_start,
+                vram: sub_vram,
+                vrom: 0,
+                size,
+                rodata: rodata.remove(&name),
+                text_symbols,
+            });
+        }
+    }
+
+    Ok(SplatSegments { family, segments })
+}
+
+/// Reads a splat config and `symbol_addrs.txt` file, see [`read_segments`].
+pub fn read_splat(
+    config_path: &Path,
+    symbol_addrs_path: &Path,
+) -> Result<SplatSegments, serde_yaml::Error> {
+    let config = fs::read_to_string(config_path).expect("Could not read splat config.");
+    let symbol_addrs =
+        fs::read_to_string(symbol_addrs_path).expect("Could not read symbol_addrs file.");
+    read_segments(&config, &parse_symbol_addrs(&symbol_addrs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_symbol_addrs() {
+        let symbols = parse_symbol_addrs(
+            "// a comment\n\
+             func_a = 0x80010000; // type:func size:0x10 rom:0x1000\n\
+             D_80020000 = 0x80020000; // type:data\n\
+             \n\
+             func_b = 0x80010010;\n",
+        );
+
+        assert_eq!(
+            symbols,
+            vec![
+                SymbolAddr {
+                    name: "func_a".to_string(),
+                    vram: 0x80010000,
+                    symbol_type: Some("func".to_string()),
+                    size: Some(0x10),
+                    rom: Some(0x1000),
+                },
+                SymbolAddr {
+                    name: "D_80020000".to_string(),
+                    vram: 0x80020000,
+                    symbol_type: Some("data".to_string()),
+                    size: None,
+                    rom: None,
+                },
+                SymbolAddr {
+                    name: "func_b".to_string(),
+                    vram: 0x80010010,
+                    symbol_type: None,
+                    size: None,
+                    rom: None,
+                },
+            ]
+        );
+        assert!(symbols[0].is_function());
+        assert!(!symbols[1].is_function());
+        assert!(symbols[2].is_function());
+    }
+
+    #[test]
+    fn test_read_segments() {
+        let config = "\
+segments:
+  - name: overlay
+    type: code
+    start: 0x100
+    vram: 0x80100000
+    subsegments:
+      - [0x80, c, before]
+      - [0x100, c, first]
+      - [0x200, c, backwards]
+      - [0x180, c, last]
+  - [0x300]
+";
+        let splat = read_segments(config, &[]).unwrap();
+        let segments = splat
+            .segments
+            .iter()
+            .map(|map| (map.object.as_str(), map.offset, map.vram, map.size))
+            .collect::<Vec<_>>();
+
+        // `before` starts ahead of its segment, `backwards` after the
+        // subsegment which follows it
+        assert_eq!(
+            segments,
+            vec![
+                ("first.o", 0x100, 0x80100000, 0x100),
+                ("last.o", 0x180, 0x80100080, 0x180),
+            ]
+        );
+
+        assert!(read_segments("segments: [", &[]).is_err());
+    }
+}
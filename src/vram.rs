@@ -0,0 +1,235 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+use serde::{Deserialize, Serialize};
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use crate::arch::mips::{self, Registers, OP_J, OP_JAL, OP_LUI, TARGET_MASK};
+use crate::MIPSFamily;
+
+// only the candidates with the most calls and references are checked
+// against the jumps
+const CANDIDATES: usize = 8;
+
+// only the most referenced targets are paired with every function start,
+// which bounds the proposals to `TARGETS` per function
+const TARGETS: usize = 32;
+
+/// How strongly the code found in a bin agrees on where it is loaded.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+/// The address a bin is loaded at, inferred from the code found in it.
+#[derive(Debug, PartialEq)]
+pub struct InferredVRAMStart {
+    pub vram_start: usize,
+    pub confidence: Confidence,
+    /// The number of calls, jumps, and address references which agree with
+    /// `vram_start`.
+    pub references: usize,
+}
+
+// the addresses a function's code refers to
+#[derive(Default)]
+struct References {
+    // the low 28 bits of `jal` targets, which start functions
+    calls: Vec<usize>,
+    // the low 28 bits of `j` targets, which stay within the function
+    jumps: Vec<(usize, Range<usize>)>,
+    // addresses built with `lui` and `addiu` or `ori`
+    addresses: Vec<usize>,
+    // the upper half of every `lui`
+    uppers: Vec<u32>,
+}
+
+impl References {
+    fn read(&mut self, code: &[u8], function: Range<usize>, family: MIPSFamily) {
+        let mut registers = Registers::new();
+        for word in code.chunks_exact(4) {
+            let instruction = mips::read_word(word, family);
+            let target = mips::jump_target(instruction);
+
+            match instruction >> 26 {
+                OP_J => self.jumps.push((target, function.clone())),
+                OP_JAL => self.calls.push(target),
+                OP_LUI => self.uppers.push(instruction & 0xFFFF),
+                _ => {}
+            }
+            if let Some(address) = registers.step(instruction) {
+                self.addresses.push(address as usize);
+            }
+        }
+    }
+}
+
+/// Infers the address a bin is loaded at from the code of the segments
+/// found in it. Each segment is the list of ranges of its functions in
+/// `bytes`.
+///
+/// Calls (`jal`) and addresses built with `lui` and `addiu` within the
+/// segments should land on the start of a function found in the bin, so
+/// each pairing of a distinct target with a function start proposes a load
+/// address. Only the most referenced targets are paired. The addresses
+/// proposed most often are then checked against the jumps (`j`)
+/// of each function, which should land within it, and against calls which
+/// land within a segment but not at a function start.
+///
+/// `j` and `jal` only encode the low 28 bits of their target. The upper
+/// bits are taken from those most common in `lui` instructions.
+pub fn infer_vram_start(
+    segments: &[Vec<Range<usize>>],
+    bytes: &[u8],
+    family: MIPSFamily,
+) -> Option<InferredVRAMStart> {
+    let mut references = References::default();
+    for function in segments.iter().flatten() {
+        if let Some(code) = bytes.get(function.clone()) {
+            references.read(code, function.clone(), family);
+        }
+    }
+
+    let starts = segments
+        .iter()
+        .flatten()
+        .map(|function| function.start)
+        .collect::<HashSet<_>>();
+    let segment_ranges = segments
+        .iter()
+        .filter_map(|functions| {
+            let start = functions.iter().map(|function| function.start).min()?;
+            let end = functions.iter().map(|function| function.end).max()?;
+            Some(start..end)
+        })
+        .collect::<Vec<_>>();
+
+    let mut regions: HashMap<u32, usize> = HashMap::new();
+    for upper in references.uppers.iter() {
+        *regions.entry(upper >> 12).or_default() += 1;
+    }
+    let region = regions
+        .into_iter()
+        .max_by_key(|(region, count)| (*count, *region))
+        .map(|(region, _)| (region as usize) << 28)
+        .unwrap_or(0);
+
+    let mut targets: HashMap<usize, usize> = HashMap::new();
+    for target in references.calls.iter().chain(references.addresses.iter()) {
+        *targets.entry(target & TARGET_MASK).or_default() += 1;
+    }
+    let mut targets = targets.into_iter().collect::<Vec<_>>();
+    targets.sort_by_key(|(target, count)| (cmp::Reverse(*count), *target));
+    targets.truncate(TARGETS);
+
+    // each of those targets paired with each function start
+    let mut proposed: HashMap<usize, usize> = HashMap::new();
+    for (target, _) in targets.iter() {
+        for start in starts.iter() {
+            *proposed
+                .entry(target.wrapping_sub(*start) & TARGET_MASK)
+                .or_default() += 1;
+        }
+    }
+    let mut proposed = proposed.into_iter().collect::<Vec<_>>();
+    proposed.sort_by_key(|(candidate, count)| (cmp::Reverse(*count), *candidate));
+    proposed.truncate(CANDIDATES);
+
+    let mut scores = proposed
+        .into_iter()
+        .map(|(candidate, _)| {
+            let offset = |target: usize| target.wrapping_sub(candidate) & TARGET_MASK;
+
+            let mut agree = 0;
+            let mut disagree = 0;
+            for target in references.calls.iter() {
+                let offset = offset(*target);
+                if starts.contains(&offset) {
+                    agree += 1;
+                } else if segment_ranges.iter().any(|range| range.contains(&offset)) {
+                    disagree += 1;
+                }
+            }
+            for address in references.addresses.iter() {
+                if (address & !TARGET_MASK) == region && starts.contains(&offset(*address)) {
+                    agree += 1;
+                }
+            }
+            for (target, function) in references.jumps.iter() {
+                if function.contains(&offset(*target)) {
+                    agree += 1;
+                } else {
+                    disagree += 1;
+                }
+            }
+
+            (
+                region | candidate,
+                agree,
+                agree as isize - disagree as isize,
+                disagree,
+            )
+        })
+        .collect::<Vec<_>>();
+    scores.sort_by_key(|(candidate, _, score, _)| (cmp::Reverse(*score), *candidate));
+
+    let (vram_start, agree, score, disagree) = *scores.first()?;
+    let runner_up = scores.get(1).map_or(0, |(_, _, score, _)| (*score).max(0));
+    if score <= runner_up {
+        return None;
+    }
+
+    let confidence = if disagree == 0 && agree >= 3 && score >= 2 * runner_up {
+        Confidence::High
+    } else if score >= 2 * runner_up {
+        Confidence::Medium
+    } else {
+        Confidence::Low
+    };
+
+    Some(InferredVRAMStart {
+        vram_start,
+        confidence,
+        references: agree,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arch::mips::assemble;
+
+    #[test]
+    fn test_infer_vram_start() {
+        let family = MIPSFamily::R3000GTE;
+
+        // two functions loaded at 0x80100000
+        let bytes = assemble(&[
+            0x0C040004, // jal   0x80100010
+            0x00000000, // nop
+            0x08040002, // j     0x80100008
+            0x00000000, // nop
+            0x3C048010, // lui   $a0, 0x8010
+            0x24840000, // addiu $a0, $a0, 0
+            0x03E00008, // jr    $ra
+            0x00000000, // nop
+        ]);
+        let segments = vec![vec![0..0x10, 0x10..0x20]];
+        assert_eq!(
+            infer_vram_start(&segments, &bytes, family),
+            Some(InferredVRAMStart {
+                vram_start: 0x80100000,
+                confidence: Confidence::High,
+                references: 3,
+            })
+        );
+
+        // code without calls or addresses says nothing about where it is
+        assert_eq!(infer_vram_start(&segments, &bytes[0x18..], family), None);
+        assert_eq!(infer_vram_start(&[], &bytes, family), None);
+    }
+}
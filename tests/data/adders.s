@@ -0,0 +1,16 @@
+    .file "adders.c"
+    .set noreorder
+    .text
+    .globl add_one
+    .type add_one, @function
+add_one:
+    jr $ra
+    addiu $v0, $a0, 1
+    .size add_one, .-add_one
+
+    .globl add_two
+    .type add_two, @function
+add_two:
+    jr $ra
+    addiu $v0, $a0, 2
+    .size add_two, .-add_two
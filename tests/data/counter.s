@@ -0,0 +1,29 @@
+    .set noreorder
+    .text
+    .type bump, @function
+bump:
+    lui $v0, %hi(counter)
+    lw $v1, %lo(counter)($v0)
+    nop
+    addiu $v1, $v1, 1
+    jr $ra
+    sw $v1, %lo(counter)($v0)
+    .size bump, .-bump
+
+    .globl next_count
+    .type next_count, @function
+next_count:
+    addiu $sp, $sp, -24
+    sw $ra, 16($sp)
+    jal bump
+    nop
+    lui $v0, %hi(counter)
+    lw $v0, %lo(counter)($v0)
+    lw $ra, 16($sp)
+    jr $ra
+    addiu $sp, $sp, 24
+    .size next_count, .-next_count
+
+    .data
+counter:
+    .word 0
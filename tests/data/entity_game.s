@@ -0,0 +1,20 @@
+    .set noreorder
+    .text
+    .globl main
+    .type main, @function
+main:
+    addiu $sp, $sp, -24
+    sw $ra, 16($sp)
+    jal init_b
+    nop
+    lw $ra, 16($sp)
+    jr $ra
+    addiu $sp, $sp, 24
+    .size main, .-main
+
+    .data
+    .globl game_state
+    .type game_state, @object
+game_state:
+    .word 7, main, 9
+    .size game_state, .-game_state
@@ -0,0 +1,31 @@
+    .set noreorder
+    .text
+    .globl init_a
+    .type init_a, @function
+init_a:
+    jr $ra
+    addiu $v0, $zero, 1
+    .size init_a, .-init_a
+
+    .globl init_b
+    .type init_b, @function
+init_b:
+    jr $ra
+    addiu $v0, $zero, 2
+    .size init_b, .-init_b
+
+    # tables which point into the overlay they are linked into
+    .data
+    .globl entity_inits
+    .type entity_inits, @object
+entity_inits:
+    .word init_a, 0x00100020
+    .word init_b, 0x00080010
+    .size entity_inits, .-entity_inits
+
+    .globl sprite_parts
+    .type sprite_parts, @object
+sprite_parts:
+    .2byte 1, 2, 3, 4
+    .word 0x12345678, entity_inits
+    .size sprite_parts, .-sprite_parts
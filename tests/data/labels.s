@@ -0,0 +1,29 @@
+# functions without sizes, like those of hand-written assembly
+    .set noreorder
+    .text
+
+    .globl sized
+    .type sized, @function
+sized:
+    jr $ra
+    addiu $v0, $zero, 1
+    .size sized, .-sized
+
+    # no `.size`
+    .globl unsized
+    .type unsized, @function
+unsized:
+    lw $v0, 0($a0)
+    jr $ra
+    nop
+
+    # no `.type` or `.size`
+    .globl label
+label:
+    addu $v0, $a0, $a1
+loop:
+    addiu $v0, $v0, -1
+    bnez $v0, loop
+    nop
+    jr $ra
+    nop
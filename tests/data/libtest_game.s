@@ -0,0 +1,13 @@
+    .set noreorder
+    .text
+    .globl main
+    .type main, @function
+main:
+    addiu $sp, $sp, -24
+    sw $ra, 16($sp)
+    jal next_count
+    nop
+    lw $ra, 16($sp)
+    jr $ra
+    addiu $sp, $sp, 24
+    .size main, .-main
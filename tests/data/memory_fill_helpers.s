@@ -0,0 +1,24 @@
+    .set noreorder
+    .set noat
+    .text
+    .globl memfill
+    .type memfill, @function
+memfill:
+    beqz $a2, 2f
+    nop
+1:
+    sb $a1, 0($a0)
+    addiu $a2, $a2, -1
+    bnez $a2, 1b
+    addiu $a0, $a0, 1
+2:
+    jr $ra
+    nop
+    .size memfill, .-memfill
+
+    .globl memzero
+    .type memzero, @function
+memzero:
+    j memfill
+    move $a1, $zero
+    .size memzero, .-memzero
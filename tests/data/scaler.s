@@ -0,0 +1,20 @@
+    .file "scaler.c"
+    .set noreorder
+    .text
+    .type double_it, @function
+double_it:
+    jr $ra
+    sll $v0, $a0, 1
+    .size double_it, .-double_it
+
+    .globl scale
+    .type scale, @function
+scale:
+    addiu $sp, $sp, -24
+    sw $ra, 16($sp)
+    jal double_it
+    nop
+    lw $ra, 16($sp)
+    jr $ra
+    addiu $sp, $sp, 24
+    .size scale, .-scale
@@ -1,49 +1,78 @@
 // SPDX-FileCopyrightText: © 2025 TTKB, LLC
 // SPDX-License-Identifier: BSD-3-CLAUSE
-use mipsmatch::fingerprint::{Fingerprint, FingerprintV0};
+use mipsmatch::fingerprint::Fingerprint;
+use mipsmatch::map::MapFormat;
+use mipsmatch::CallSite;
 use mipsmatch::MIPSFamily;
 use mipsmatch::Options;
+use mipsmatch::RODataSignatureType;
+use mipsmatch::RelocationMask;
 use mipsmatch::SegmentSignature;
-use serde::Deserialize;
+use mipsmatch::Signature;
+use serde::de::DeserializeOwned;
 use serde_yaml::{self};
 use std::io::{self, Cursor, Write};
-use std::path::Path;
-use std::str::FromStr;
+use std::path::{Path, PathBuf};
 
-// PS1
-#[test]
-fn test_tt_004() {
+// runs `fingerprint` with the default options and parses each document it
+// writes
+fn fingerprint_documents<T: DeserializeOwned>(
+    fingerprint: impl FnOnce(&mut Options<Cursor<Vec<u8>>>),
+) -> Vec<T> {
     let buff = Cursor::new(Vec::new());
-
     let mut options = Options::new(buff);
-
-    mipsmatch::fingerprint::fingerprint(
-        &Path::new("tests/data/tt_004.map"),
-        &Path::new("tests/data/tt_004.elf"),
-        &mut options,
-    );
+    fingerprint(&mut options);
 
     let config = String::from_utf8(options.writer.into_inner()).unwrap();
+    serde_yaml::Deserializer::from_str(config.as_str())
+        .map(|document| T::deserialize(document).unwrap())
+        .collect()
+}
 
-    println!("cursor: {}", config);
-    io::stdout().flush().unwrap();
-
-    let mut i = 0;
-    for document in serde_yaml::Deserializer::from_str(config.as_str()) {
-        let segment = SegmentSignature::deserialize(document).unwrap();
+// PS1
+#[test]
+fn test_tt_004() {
+    let segments: Vec<SegmentSignature> = fingerprint_documents(|options| {
+        mipsmatch::fingerprint::fingerprint(
+            Path::new("tests/data/tt_004.map"),
+            Path::new("tests/data/tt_004.elf"),
+            options,
+        )
+    });
 
+    for (i, segment) in segments.iter().enumerate() {
         println!("doc: {:?}", segment);
         io::stdout().flush().unwrap();
 
         match i {
-            0 => assert_sword(&segment),
-            1 => assert_servant_common(&segment),
+            0 => {
+                assert_sword(segment);
+                assert_sword_rodata(segment);
+
+                // `jal goodbye_world` and the `j`s of hello_world's switch
+                let references = segment
+                    .references
+                    .iter()
+                    .map(|reference| (reference.offset, reference.address))
+                    .collect::<Vec<_>>();
+                assert_eq!(references[0], (0x3C, 0x80170988));
+                assert_eq!(references.len(), 6);
+
+                let hello_world = &segment.functions[1];
+                assert_eq!(
+                    hello_world.calls,
+                    vec![CallSite {
+                        offset: 0x2C,
+                        name: "goodbye_world".to_string(),
+                    }]
+                );
+            }
+            1 => assert_servant_common(segment),
             _ => (),
         }
-        i += 1;
     }
 
-    assert_eq!(i, 2);
+    assert_eq!(segments.len(), 2);
 }
 
 /*
@@ -68,17 +97,27 @@ fn assert_sword(segment: &SegmentSignature) {
     assert_eq!(segment.family, MIPSFamily::R3000GTE);
     assert_eq!(segment.fingerprint, Fingerprint::new_v0(128, 0x344d1662));
     assert_eq!(segment.size, 128);
+    assert!(segment.digest.is_some());
     assert_eq!(segment.functions.len(), 2);
 
-    let f0 = segment.functions.get(0).expect("functions[0]");
+    let f0 = segment.functions.first().expect("functions[0]");
     assert_eq!(f0.name, "goodbye_world");
     assert_eq!(f0.fingerprint, Fingerprint::new_v0(16, 0xd2c44fb0));
+    assert!(f0.digest.is_some());
 
     let f1 = segment.functions.get(1).expect("functions[1]");
     assert_eq!(f1.name, "hello_world");
     assert_eq!(f1.fingerprint, Fingerprint::new_v0(112, 0x8b4b9bb1));
 }
 
+// the string "goodbye, world." followed by the jump table of hello_world
+fn assert_sword_rodata(segment: &SegmentSignature) {
+    let rodata = segment.rodata.as_ref().expect("sword rodata");
+    assert_eq!(rodata.rodata_type, RODataSignatureType::EndsWithJumpTable);
+    assert_eq!(rodata.size, 0x34);
+    assert_eq!(rodata.fingerprint, None);
+}
+
 /*
 name: servant_common
 fingerprint: urn:decomp:match:fingerprint:0:84:418d4b82
@@ -100,9 +139,10 @@ fn assert_servant_common(segment: &SegmentSignature) {
     assert_eq!(segment.family, MIPSFamily::R3000GTE);
     assert_eq!(segment.fingerprint, Fingerprint::new_v0(84, 0x418d4b82));
     assert_eq!(segment.size, 84);
+    assert_eq!(segment.rodata, None);
     assert_eq!(segment.functions.len(), 3);
 
-    let f0 = segment.functions.get(0).expect("functions[0]");
+    let f0 = segment.functions.first().expect("functions[0]");
     assert_eq!(f0.name, "local_function");
     assert_eq!(f0.fingerprint, Fingerprint::new_v0(16, 0x3ac45786));
 
@@ -115,6 +155,401 @@ fn assert_servant_common(segment: &SegmentSignature) {
     assert_eq!(f2.fingerprint, Fingerprint::new_v0(36, 0x43e9eef6));
 }
 
+#[test]
+fn test_tt_004_without_map() {
+    // grouped by STT_FILE symbols
+    let segments: Vec<SegmentSignature> = fingerprint_documents(|options| {
+        mipsmatch::fingerprint::fingerprint_elf(Path::new("tests/data/tt_004.elf"), options)
+    });
+
+    assert_eq!(segments.len(), 2);
+    assert_sword(&segments[0]);
+    assert_servant_common(&segments[1]);
+}
+
+#[test]
+fn test_dwarf_without_map() {
+    // no STT_FILE symbols, grouped by DWARF compile units
+    let segments: Vec<SegmentSignature> = fingerprint_documents(|options| {
+        mipsmatch::fingerprint::fingerprint_elf(
+            Path::new("tests/data/libtest_game_dwarf.elf"),
+            options,
+        )
+    });
+
+    let groups = segments
+        .iter()
+        .map(|segment| {
+            (
+                segment.name.as_str(),
+                segment.size,
+                segment
+                    .functions
+                    .iter()
+                    .map(|function| function.name.as_str())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        groups,
+        vec![
+            ("game", 0x1C, vec!["main"]),
+            ("counter", 0x3C, vec!["bump", "next_count"]),
+            ("memory_fill_helpers", 0x28, vec!["memfill", "memzero"]),
+        ]
+    );
+
+    // the same code as the objects it was linked from
+    assert_eq!(segments[2].fingerprint, Fingerprint::new_v0(40, 0x4c2db66e));
+}
+
+#[test]
+fn test_global_functions_without_map() {
+    // scaler.c has a static function, adders.c only global functions
+    let groups = |elf: &str| {
+        fingerprint_documents::<SegmentSignature>(|options| {
+            mipsmatch::fingerprint::fingerprint_elf(Path::new(elf), options)
+        })
+        .into_iter()
+        .map(|segment| {
+            let functions = segment
+                .functions
+                .into_iter()
+                .map(|function| function.name)
+                .collect::<Vec<_>>();
+            (segment.name, functions)
+        })
+        .collect::<Vec<_>>()
+    };
+
+    // the global functions are told apart by their compile units
+    assert_eq!(
+        groups("tests/data/globals_dwarf.elf"),
+        vec![
+            (
+                "scaler".to_string(),
+                vec!["double_it".to_string(), "scale".to_string()]
+            ),
+            (
+                "adders".to_string(),
+                vec!["add_one".to_string(), "add_two".to_string()]
+            ),
+        ]
+    );
+
+    // without them, each global function past the static one is a segment
+    // of its own rather than part of scaler.c
+    assert_eq!(
+        groups("tests/data/globals.elf"),
+        vec![
+            ("scaler".to_string(), vec!["double_it".to_string()]),
+            ("scale".to_string(), vec!["scale".to_string()]),
+            ("add_one".to_string(), vec!["add_one".to_string()]),
+            ("add_two".to_string(), vec!["add_two".to_string()]),
+        ]
+    );
+}
+
+#[test]
+fn test_tt_004_splat() {
+    let segments: Vec<SegmentSignature> = fingerprint_documents(|options| {
+        mipsmatch::fingerprint::fingerprint_splat(
+            Path::new("tests/data/tt_004.splat.yaml"),
+            Path::new("tests/data/tt_004_symbol_addrs.txt"),
+            Path::new("tests/data/tt_004_raw.bin"),
+            options,
+        )
+        .unwrap()
+    });
+
+    // the same signatures as the map and elf the overlay was linked from
+    assert_eq!(segments.len(), 2);
+    assert_sword(&segments[0]);
+    assert_sword_rodata(&segments[0]);
+    assert_servant_common(&segments[1]);
+}
+
+// fingerprints libtest_lld.elf with a map in `format`
+fn libtest_segments(map_file: &str, format: MapFormat) -> Vec<SegmentSignature> {
+    fingerprint_documents(|options| {
+        mipsmatch::fingerprint::fingerprint_map(
+            Path::new(map_file),
+            format,
+            Path::new("tests/data/libtest_lld.elf"),
+            options,
+        )
+    })
+}
+
+#[test]
+fn test_map_formats() {
+    let lld = libtest_segments("tests/data/libtest_lld.map", MapFormat::Auto);
+    let names = lld
+        .iter()
+        .map(|segment| segment.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec!["game", "libtest/memory_fill_helpers", "libtest/counter"]
+    );
+    assert_eq!(lld[1].fingerprint, Fingerprint::new_v0(40, 0x4c2db66e));
+    assert_eq!(lld[2].functions.len(), 2);
+
+    // the same link described by each linker
+    for (map_file, format) in [
+        ("tests/data/libtest_lld.map", MapFormat::Lld),
+        ("tests/data/libtest_gold.map", MapFormat::Auto),
+        ("tests/data/libtest_gold.map", MapFormat::Gold),
+        ("tests/data/libtest_mwcc.map", MapFormat::Auto),
+        ("tests/data/libtest_mwcc.map", MapFormat::Mwcc),
+    ] {
+        assert_eq!(
+            libtest_segments(map_file, format),
+            lld,
+            "{} as {:?}",
+            map_file,
+            format
+        );
+    }
+}
+
+// fingerprints tests/data/labels.o, whose functions are missing sizes
+fn labels_functions(include_labels: bool) -> Vec<(String, usize, bool)> {
+    let segments: Vec<SegmentSignature> = fingerprint_documents(|options| {
+        options.include_labels = include_labels;
+        mipsmatch::fingerprint::fingerprint_lib(&[PathBuf::from("tests/data/labels.o")], options)
+    });
+
+    assert_eq!(segments.len(), 1);
+    segments[0]
+        .functions
+        .iter()
+        .map(|function| (function.name.clone(), function.size, function.size_inferred))
+        .collect()
+}
+
+#[test]
+fn test_inferred_sizes() {
+    // `unsized` ends at the `label` which follows it
+    assert_eq!(
+        labels_functions(false),
+        vec![
+            ("sized".to_string(), 0x8, false),
+            ("unsized".to_string(), 0xC, true),
+        ]
+    );
+
+    // `label` ends with the section, not at the local `loop` label
+    assert_eq!(
+        labels_functions(true),
+        vec![
+            ("sized".to_string(), 0x8, false),
+            ("unsized".to_string(), 0xC, true),
+            ("label".to_string(), 0x18, true),
+        ]
+    );
+}
+
+#[test]
+fn test_mdebug() {
+    // libtest_lld.elf without a symbol for the static `bump`, but with
+    // `.mdebug` file and procedure descriptors
+    let elf = Path::new("tests/data/libtest_mdebug.elf");
+
+    let segments: Vec<SegmentSignature> =
+        fingerprint_documents(|options| mipsmatch::fingerprint::fingerprint_elf(elf, options));
+
+    let groups = segments
+        .iter()
+        .map(|segment| {
+            (
+                segment.name.as_str(),
+                segment.size,
+                segment
+                    .functions
+                    .iter()
+                    .map(|function| function.name.as_str())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        groups,
+        vec![
+            ("game", 0x1C, vec!["main"]),
+            ("memory_fill_helpers", 0x28, vec!["memfill", "memzero"]),
+            ("counter", 0x3C, vec!["bump", "next_count"]),
+        ]
+    );
+
+    // the map does not list `bump`, but `.mdebug` does
+    let segments: Vec<SegmentSignature> = fingerprint_documents(|options| {
+        mipsmatch::fingerprint::fingerprint_map(
+            Path::new("tests/data/libtest_lld.map"),
+            MapFormat::Auto,
+            elf,
+            options,
+        )
+    });
+    assert_eq!(
+        segments,
+        libtest_segments("tests/data/libtest_lld.map", MapFormat::Auto)
+    );
+}
+
+#[test]
+fn test_overlays_sharing_vram() {
+    // the objects of libtest.a linked as two overlays at 0x80100000, see
+    // overlays.ld
+    let segments: Vec<SegmentSignature> = fingerprint_documents(|options| {
+        mipsmatch::fingerprint::fingerprint(
+            Path::new("tests/data/overlays.map"),
+            Path::new("tests/data/overlays.elf"),
+            options,
+        )
+    });
+
+    let overlays = segments
+        .iter()
+        .map(|segment| {
+            (
+                segment.name.as_str(),
+                segment.fingerprint,
+                segment
+                    .functions
+                    .iter()
+                    .map(|function| function.name.as_str())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        overlays,
+        vec![
+            (
+                "memory_fill_helpers",
+                Fingerprint::new_v0(40, 0x4c2db66e),
+                vec!["memfill", "memzero"]
+            ),
+            (
+                "counter",
+                Fingerprint::new_v0(60, 0x9f87ca6),
+                vec!["bump", "next_count"]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_data_signature() {
+    // a table of pointers to functions and a table with a pointer into the
+    // first, see entity_table.s
+    let signatures: Vec<Signature> = fingerprint_documents(|options| {
+        options.include_data = true;
+        mipsmatch::fingerprint::fingerprint(
+            Path::new("tests/data/entity_table.map"),
+            Path::new("tests/data/entity_table.elf"),
+            options,
+        )
+    });
+    assert_eq!(signatures.len(), 2);
+
+    let Signature::Segment(ref segment) = signatures[0] else {
+        panic!("expected a segment signature");
+    };
+    assert_eq!(segment.name, "entity_table");
+
+    let Signature::Data(ref data) = signatures[1] else {
+        panic!("expected a data signature");
+    };
+    assert_eq!(data.name, "entity_table");
+    assert_eq!(data.section, ".data");
+    assert_eq!(data.size, 0x20);
+    // the longest run without pointers, less the leading zero word
+    assert_eq!(data.anchor, 0xC);
+    assert_eq!(data.fingerprint, Fingerprint::new_v0(16, 0xae438adc));
+    assert_eq!(
+        data.relocations
+            .iter()
+            .map(|relocation| (relocation.offset, relocation.mask))
+            .collect::<Vec<_>>(),
+        vec![(0x0, 0xFFFFFFFF), (0x8, 0xFFFFFFFF), (0x1C, 0xFFFFFFFF)]
+    );
+    assert_eq!(data.symbols.get("entity_inits"), Some(&0x0));
+    assert_eq!(data.symbols.get("sprite_parts"), Some(&0x10));
+}
+
+#[test]
+fn test_tt_004_archive_member() {
+    // servant_common linked from `build/us/lib/libservant.a`
+    let segments: Vec<SegmentSignature> = fingerprint_documents(|options| {
+        mipsmatch::fingerprint::fingerprint(
+            Path::new("tests/data/tt_004_archive.map"),
+            Path::new("tests/data/tt_004.elf"),
+            options,
+        )
+    });
+
+    assert_eq!(segments.len(), 2);
+    assert_sword(&segments[0]);
+    assert_eq!(segments[1].name, "libservant/servant_common");
+    assert_eq!(segments[1].fingerprint, Fingerprint::new_v0(84, 0x418d4b82));
+    assert_eq!(segments[1].functions.len(), 3);
+}
+
+#[test]
+fn test_fingerprint_lib() {
+    let segments: Vec<SegmentSignature> = fingerprint_documents(|options| {
+        mipsmatch::fingerprint::fingerprint_lib(
+            &[
+                PathBuf::from("tests/data/libtest.a"),
+                PathBuf::from("tests/data/counter.o"),
+            ],
+            options,
+        )
+    });
+
+    // every archive member, then the loose object
+    let names = segments
+        .iter()
+        .map(|segment| segment.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec!["libtest/memory_fill_helpers", "libtest/counter", "counter"]
+    );
+    assert_eq!(segments[1].fingerprint, segments[2].fingerprint);
+    assert_eq!(segments[1].digest, segments[2].digest);
+
+    let helpers = &segments[0];
+    assert_eq!(helpers.family, MIPSFamily::R3000GTE);
+    assert_eq!(helpers.size, 0x28);
+    let functions = helpers
+        .functions
+        .iter()
+        .map(|function| (function.name.as_str(), function.size))
+        .collect::<Vec<_>>();
+    assert_eq!(functions, vec![("memfill", 0x20), ("memzero", 0x8)]);
+
+    // `j memfill` is relocated, and unlinked targets are not references
+    assert!(helpers.references.is_empty());
+    let memzero = &helpers.functions[1];
+    assert_eq!(
+        memzero.relocations,
+        Some(vec![RelocationMask {
+            offset: 0,
+            mask: 0x03FFFFFF,
+        }])
+    );
+
+    // static functions are included
+    let counter = &segments[1];
+    assert_eq!(counter.functions[0].name, "bump");
+    assert_eq!(counter.functions[1].name, "next_count");
+    assert_eq!(counter.relocations.as_ref().map(|r| r.len()), Some(6));
+}
+
 /*
 // N64
 #[test]
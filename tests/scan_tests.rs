@@ -1,18 +1,25 @@
 // SPDX-FileCopyrightText: © 2025 TTKB, LLC
 // SPDX-License-Identifier: BSD-3-CLAUSE
-use mipsmatch::fingerprint::{Fingerprint, FingerprintV0};
+use mipsmatch::fingerprint::{digest_for_relocated_range, Fingerprint};
 use mipsmatch::rk::RabinKarpMIPSHasher;
-use mipsmatch::FunctionSignature;
 use mipsmatch::MIPSFamily;
 use mipsmatch::Options;
+use mipsmatch::RelocationMask;
+use mipsmatch::SegmentOffset;
+use mipsmatch::SegmentSignature;
+use mipsmatch::SerializeToYAML;
+use mipsmatch::{FunctionOffset, FunctionSignature};
+use mipsmatch::{JumpTableOffset, RODataOffset, RODataSignature, RODataSignatureType};
 use serde::Deserialize;
-use serde_yaml::{self};
-use std::io::{self, Cursor, Write};
-use std::path::Path;
-use std::str::FromStr;
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process;
 
 use mipsmatch::arch::mips;
-use mipsmatch::scan;
+use mipsmatch::delta::DeltaCheck;
+use mipsmatch::scan::{self, Granularity, ScanOptions};
 
 #[test]
 fn test_004() {
@@ -20,6 +27,10 @@ fn test_004() {
         name: "goodbye_world".to_string(),
         fingerprint: Fingerprint::new_v0(16, 0xd2c44fb0),
         size: 16,
+        size_inferred: false,
+        digest: None,
+        relocations: None,
+        calls: Vec::new(),
     };
 
     let bytes = std::fs::read("tests/data/TT_004.BIN").expect("Could not read bin file");
@@ -42,3 +53,712 @@ fn test_004() {
     assert_eq!(i, Some(0x988));
     assert_eq!(hasher.find(0xd2c44fb0, 16, &bytes), Some(0x988));
 }
+
+// a file in the temporary directory, removed when dropped so that failing
+// tests do not leave it behind
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str, contents: impl AsRef<[u8]>) -> Self {
+        let file =
+            TempFile(std::env::temp_dir().join(format!("mipsmatch-{}-{}", process::id(), name)));
+        file.write(contents);
+        file
+    }
+
+    fn write(&self, contents: impl AsRef<[u8]>) {
+        std::fs::write(&self.0, contents).expect("write temporary file");
+    }
+}
+
+impl Deref for TempFile {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempFile {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+// writes a match file for tt_004 to a temporary location
+fn tt_004_match_file(name: &str) -> TempFile {
+    tt_004_match_file_for(name, Path::new("tests/data/tt_004.elf"))
+}
+
+// writes a match file for tt_004 linked as `elf_file`
+fn tt_004_match_file_for(name: &str, elf_file: &Path) -> TempFile {
+    let buff = Cursor::new(Vec::new());
+    let mut options = Options::new(buff);
+
+    mipsmatch::fingerprint::fingerprint(Path::new("tests/data/tt_004.map"), elf_file, &mut options);
+
+    TempFile::new(&format!("{}.yaml", name), options.writer.into_inner())
+}
+
+fn scan_output(match_file: &Path, bin_file: &Path, granularity: Granularity) -> String {
+    let scan_options = ScanOptions {
+        granularity,
+        ..Default::default()
+    };
+    scan_output_with(match_file, bin_file, &scan_options)
+}
+
+fn scan_output_with(match_file: &Path, bin_file: &Path, scan_options: &ScanOptions) -> String {
+    let buff = Cursor::new(Vec::new());
+    let mut options = Options::new(buff);
+
+    scan::scan(
+        &vec![match_file.to_path_buf()],
+        bin_file,
+        scan_options,
+        &mut options,
+    );
+
+    String::from_utf8(options.writer.into_inner()).unwrap()
+}
+
+fn scan_results(match_file: &Path, bin_file: &Path) -> Vec<SegmentOffset> {
+    let output = scan_output(match_file, bin_file, Granularity::Segment);
+    serde_yaml::Deserializer::from_str(&output)
+        .map(|document| SegmentOffset::deserialize(document).unwrap())
+        .collect()
+}
+
+fn function_results(match_file: &Path, bin_file: &Path) -> Vec<(String, usize)> {
+    let output = scan_output(match_file, bin_file, Granularity::Function);
+    serde_yaml::Deserializer::from_str(&output)
+        .map(|document| FunctionOffset::deserialize(document).unwrap())
+        .map(|fo| (fo.name, fo.offset))
+        .collect()
+}
+
+#[test]
+fn test_scan_library_fingerprints() {
+    let buff = Cursor::new(Vec::new());
+    let mut options = Options::new(buff);
+    mipsmatch::fingerprint::fingerprint_lib(&[PathBuf::from("tests/data/libtest.a")], &mut options);
+
+    let match_file = TempFile::new("lib.yaml", options.writer.into_inner());
+
+    // `main` from libtest_game.s linked in front of both archive members
+    let results = scan_results(&match_file, Path::new("tests/data/libtest_game.bin"));
+    let offsets = results
+        .iter()
+        .map(|so| (so.name.as_str(), so.offset))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        offsets,
+        vec![
+            ("libtest/counter", 0x20),
+            ("libtest/memory_fill_helpers", 0x60)
+        ]
+    );
+    assert_eq!(results[0].symbols.get("next_count"), Some(&0x38));
+}
+
+#[test]
+fn test_scan_callees() {
+    let buff = Cursor::new(Vec::new());
+    let mut options = Options::new(buff);
+    mipsmatch::fingerprint::fingerprint(
+        Path::new("tests/data/libtest_lld.map"),
+        Path::new("tests/data/libtest_lld.elf"),
+        &mut options,
+    );
+
+    let match_file = TempFile::new("callees.yaml", options.writer.into_inner());
+
+    // `main` calls `next_count`, whose segment no longer matches once
+    // `bump` is edited (`addiu` to `ori`)
+    let mut bin = std::fs::read("tests/data/libtest_game.bin").expect("Could not read bin file");
+    bin[0x2F] = 0x34;
+    let bin_file = TempFile::new("callees.bin", &bin);
+
+    let scan_options = ScanOptions {
+        granularity: Granularity::Segment,
+        vram_start: Some(0x80010000),
+        ..Default::default()
+    };
+    let output = scan_output_with(&match_file, &bin_file, &scan_options);
+    let results = serde_yaml::Deserializer::from_str(&output)
+        .map(|document| SegmentOffset::deserialize(document).unwrap())
+        .collect::<Vec<_>>();
+    let names = results
+        .iter()
+        .map(|so| so.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["game", "libtest/memory_fill_helpers"]);
+    assert_eq!(
+        results[0].callees,
+        HashMap::from([("next_count".to_string(), 0x38)])
+    );
+
+    // calls within a segment are already named by its symbols
+    assert!(results[1].callees.is_empty());
+}
+
+#[test]
+fn test_scan_every_occurrence() {
+    let match_file = tt_004_match_file("every-occurrence");
+
+    // the overlay twice, back to back
+    let raw = std::fs::read("tests/data/tt_004_raw.bin").expect("Could not read bin file");
+    let mut doubled = raw.clone();
+    doubled.extend_from_slice(&raw);
+    let bin_file = TempFile::new("doubled.bin", &doubled);
+
+    let mut results = scan_results(&match_file, &bin_file)
+        .iter()
+        .map(|so| (so.name.clone(), so.offset))
+        .collect::<Vec<_>>();
+    results.sort();
+
+    assert_eq!(
+        results,
+        vec![
+            ("servant_common".to_string(), 0xA08),
+            ("servant_common".to_string(), raw.len() + 0xA08),
+            ("sword".to_string(), 0x988),
+            ("sword".to_string(), raw.len() + 0x988),
+        ]
+    );
+}
+
+#[test]
+fn test_scan_in_chunks() {
+    let match_file = tt_004_match_file("chunks");
+
+    let raw = std::fs::read("tests/data/tt_004_raw.bin").expect("Could not read bin file");
+    let mut doubled = raw.clone();
+    doubled.extend_from_slice(&raw);
+    let bin_file = TempFile::new("chunks.bin", &doubled);
+
+    let whole = scan_output(&match_file, &bin_file, Granularity::All);
+    assert!(whole.contains("sword"));
+
+    // chunks smaller than a segment, unaligned, and splitting the copies
+    for chunk_size in [0x40, 0x99A, raw.len()] {
+        let scan_options = ScanOptions {
+            chunk_size,
+            ..Default::default()
+        };
+        assert_eq!(
+            scan_output_with(&match_file, &bin_file, &scan_options),
+            whole,
+            "chunk size 0x{:X}",
+            chunk_size
+        );
+    }
+}
+
+#[test]
+fn test_scan_rejects_digest_mismatch() {
+    let match_file = tt_004_match_file("digest-mismatch");
+    let config = std::fs::read_to_string(&match_file).expect("read match file");
+
+    // keep the fingerprints, but claim different instructions were hashed
+    let mut tampered = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(&config) {
+        let mut segment = SegmentSignature::deserialize(document).unwrap();
+        assert!(segment.digest.is_some());
+        if segment.name == "sword" {
+            segment.digest = segment.digest.map(|d| d ^ 1);
+            for function in segment.functions.iter_mut() {
+                function.digest = function.digest.map(|d| d ^ 1);
+            }
+        }
+        writeln!(tampered, "---").unwrap();
+        segment.serialize_to_yaml(&mut tampered);
+    }
+    match_file.write(tampered);
+
+    let results = scan_results(&match_file, Path::new("tests/data/TT_004.BIN"));
+    let names = results
+        .iter()
+        .map(|so| so.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["servant_common"]);
+}
+
+#[test]
+fn test_scan_functions() {
+    let match_file = tt_004_match_file("functions");
+
+    // every function is unique in the original overlay
+    assert_eq!(
+        function_results(&match_file, Path::new("tests/data/TT_004.BIN")),
+        vec![
+            ("goodbye_world".to_string(), 0x988),
+            ("hello_world".to_string(), 0x998),
+            ("local_function".to_string(), 0xA08),
+            ("global_function".to_string(), 0xA18),
+            ("global_function_2".to_string(), 0xA38),
+        ]
+    );
+
+    // break `sword` by dropping its first function. the segment no longer
+    // matches, but `hello_world` is still found on its own.
+    let raw = std::fs::read("tests/data/tt_004_raw.bin").expect("Could not read bin file");
+    let mut broken = raw[..0x988].to_vec();
+    broken.extend_from_slice(&raw[0x998..]);
+    let bin_file = TempFile::new("broken.bin", &broken);
+
+    let segments = scan_results(&match_file, &bin_file);
+    assert_eq!(segments.len(), 1);
+    assert_eq!(segments[0].name, "servant_common");
+
+    let output = scan_output(&match_file, &bin_file, Granularity::All);
+    let documents = serde_yaml::Deserializer::from_str(&output)
+        .map(|document| serde_yaml::Value::deserialize(document).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(documents.len(), 2);
+    // the full segment is reported first
+    assert_eq!(documents[0]["name"].as_str(), Some("servant_common"));
+    assert_eq!(documents[1]["name"].as_str(), Some("hello_world"));
+    assert_eq!(documents[1]["offset"].as_u64(), Some(0x988));
+    assert!(documents[1].get("symbols").is_none());
+}
+
+#[test]
+fn test_scan_partial_segment() {
+    let match_file = tt_004_match_file("partial");
+
+    // drop `local_function` from `servant_common`
+    let raw = std::fs::read("tests/data/tt_004_raw.bin").expect("Could not read bin file");
+    let mut edited = raw[..0xA08].to_vec();
+    edited.extend_from_slice(&raw[0xA18..]);
+    let bin_file = TempFile::new("edited.bin", &edited);
+
+    // partial matches are only reported when asked for
+    let results = scan_results(&match_file, &bin_file);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].name, "sword");
+
+    let scan_options = ScanOptions {
+        granularity: Granularity::Segment,
+        min_coverage: Some(0.5),
+        ..Default::default()
+    };
+    let output = scan_output_with(&match_file, &bin_file, &scan_options);
+    let mut results = serde_yaml::Deserializer::from_str(&output)
+        .map(|document| SegmentOffset::deserialize(document).unwrap())
+        .collect::<Vec<_>>();
+    results.sort_by_key(|so| so.offset);
+    assert_eq!(results.len(), 2);
+
+    assert_eq!(results[0].name, "sword");
+    assert_eq!(results[0].coverage, None);
+    assert!(results[0].missing.is_empty());
+
+    let partial = &results[1];
+    assert_eq!(partial.name, "servant_common");
+    assert_eq!(partial.offset, 0xA08);
+    assert_eq!(partial.size, 0x44);
+    assert!((partial.coverage.unwrap() - 2.0 / 3.0).abs() < 0.01);
+    assert_eq!(partial.missing, vec!["local_function".to_string()]);
+    assert_eq!(partial.symbols.get("global_function"), Some(&0xA08));
+    assert_eq!(partial.symbols.get("global_function_2"), Some(&0xA28));
+}
+
+#[test]
+fn test_scan_padded_segment() {
+    let match_file = tt_004_match_file("padded");
+
+    // add two words of alignment padding between the `sword` functions
+    let raw = std::fs::read("tests/data/tt_004_raw.bin").expect("Could not read bin file");
+    let mut padded = raw[..0x998].to_vec();
+    padded.extend_from_slice(&[0; 8]);
+    padded.extend_from_slice(&raw[0x998..]);
+    let bin_file = TempFile::new("padded.bin", &padded);
+
+    let segments_with_padding = |max_padding| {
+        let scan_options = ScanOptions {
+            granularity: Granularity::Segment,
+            max_padding,
+            ..Default::default()
+        };
+        let output = scan_output_with(&match_file, &bin_file, &scan_options);
+        let mut results = serde_yaml::Deserializer::from_str(&output)
+            .map(|document| SegmentOffset::deserialize(document).unwrap())
+            .collect::<Vec<_>>();
+        results.sort_by_key(|so| so.offset);
+        results
+    };
+
+    // the segment fingerprint no longer matches
+    let results = segments_with_padding(None);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].name, "servant_common");
+
+    // not enough padding allowed
+    assert_eq!(segments_with_padding(Some(1)).len(), 1);
+
+    let results = segments_with_padding(Some(2));
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].name, "sword");
+    assert_eq!(results[0].offset, 0x988);
+    assert_eq!(results[0].size, 0x88);
+    assert_eq!(results[0].symbols.get("goodbye_world"), Some(&0x988));
+    assert_eq!(results[0].symbols.get("hello_world"), Some(&0x9A0));
+    assert_eq!(results[1].name, "servant_common");
+    assert_eq!(results[1].offset, 0xA10);
+}
+
+#[test]
+fn test_scan_relocations() {
+    let match_file = tt_004_match_file("opcode-masks");
+    let relocs_file = tt_004_match_file_for(
+        "relocation-masks",
+        Path::new("tests/data/tt_004_relocs.elf"),
+    );
+
+    let config = std::fs::read_to_string(&relocs_file).expect("read match file");
+    for document in serde_yaml::Deserializer::from_str(&config) {
+        let segment = SegmentSignature::deserialize(document).unwrap();
+        assert!(segment.relocations.is_some());
+        if segment.name == "servant_common" {
+            // `lui`/`lw` in local_function and `jal` in global_function
+            let relocations = segment.functions[0].relocations.as_ref().unwrap();
+            assert_eq!(
+                relocations,
+                &vec![
+                    RelocationMask {
+                        offset: 0x0,
+                        mask: 0x0000FFFF
+                    },
+                    RelocationMask {
+                        offset: 0x4,
+                        mask: 0x0000FFFF
+                    },
+                ]
+            );
+            let relocations = segment.functions[1].relocations.as_ref().unwrap();
+            assert_eq!(relocations[0].offset, 0x8);
+            assert_eq!(relocations[0].mask, 0x03FFFFFF);
+        }
+    }
+
+    let names = |match_file: &Path, bin_file: &Path| {
+        let mut names = scan_results(match_file, bin_file)
+            .into_iter()
+            .map(|so| (so.name, so.offset))
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+    let both = vec![
+        ("servant_common".to_string(), 0xA08),
+        ("sword".to_string(), 0x988),
+    ];
+
+    let raw = std::fs::read("tests/data/tt_004_raw.bin").expect("Could not read bin file");
+
+    // relocated to a different address, `lui` in goodbye_world
+    let mut relocated = raw.clone();
+    relocated[0x988] = 0x18;
+    let bin_file = TempFile::new("relocs.bin", &relocated);
+    assert_eq!(names(&match_file, &bin_file), both);
+    assert_eq!(names(&relocs_file, &bin_file), both);
+
+    // a different constant, `ori $2, $zero, 123` in hello_world, is only
+    // noticed with relocation masks
+    let mut edited = raw.clone();
+    edited[0x9F4] = 124;
+    bin_file.write(&edited);
+    assert_eq!(names(&match_file, &bin_file), both);
+    assert_eq!(
+        names(&relocs_file, &bin_file),
+        vec![("servant_common".to_string(), 0xA08)]
+    );
+}
+
+#[test]
+fn test_scan_delta_check() {
+    let match_file = tt_004_match_file("delta");
+    let raw = std::fs::read("tests/data/tt_004_raw.bin").expect("Could not read bin file");
+    let bin_file = TempFile::new("delta.bin", &raw);
+
+    let sword = |bin: &[u8]| {
+        bin_file.write(bin);
+        scan_results(&match_file, &bin_file)
+            .into_iter()
+            .find(|so| so.name == "sword")
+            .map(|so| (so.offset, so.delta_check, so.vram_delta))
+    };
+
+    assert_eq!(
+        sword(&raw),
+        Some((0x988, Some(DeltaCheck::Consistent), Some(0)))
+    );
+
+    // the `jal goodbye_world` and `j` instructions of hello_world, as if
+    // sword had been linked 0x100 bytes lower
+    let jumps = [0x9C4, 0x9CC, 0x9D4, 0x9DC, 0x9E4, 0x9EC];
+    let mut moved = raw.clone();
+    for offset in jumps {
+        moved[offset] -= 0x40;
+    }
+    assert_eq!(
+        sword(&moved),
+        Some((0x988, Some(DeltaCheck::Consistent), Some(-0x100)))
+    );
+
+    // only some of them moved
+    let mut inconsistent = raw.clone();
+    inconsistent[jumps[0]] -= 0x40;
+    assert_eq!(
+        sword(&inconsistent),
+        Some((0x988, Some(DeltaCheck::Inconsistent), None))
+    );
+}
+
+#[test]
+fn test_scan_data() {
+    let buff = Cursor::new(Vec::new());
+    let mut options = Options::new(buff);
+    options.include_data = true;
+
+    mipsmatch::fingerprint::fingerprint(
+        Path::new("tests/data/entity_table.map"),
+        Path::new("tests/data/entity_table.elf"),
+        &mut options,
+    );
+
+    let match_file = TempFile::new("data.yaml", options.writer.into_inner());
+
+    // entity_table linked after entity_game at a different address, so
+    // every pointer in its data differs
+    let results = scan_results(&match_file, Path::new("tests/data/entity_game.bin"));
+    let found = results
+        .iter()
+        .map(|so| (so.name.as_str(), so.section.as_deref(), so.offset, so.size))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        vec![
+            ("entity_table", None, 0x20, 0x10),
+            ("entity_table", Some(".data"), 0x70, 0x20),
+        ]
+    );
+
+    let data = &results[1];
+    assert_eq!(data.symbols.get("entity_inits"), Some(&0x70));
+    assert_eq!(data.symbols.get("sprite_parts"), Some(&0x80));
+
+    // data is not searched for when only looking for functions
+    let output = scan_output(
+        &match_file,
+        Path::new("tests/data/entity_game.bin"),
+        Granularity::Function,
+    );
+    assert!(!output.contains(".data"));
+
+    // a copy of the data whose pointers point outside of the bin is told
+    // apart from the original knowing where the bin is loaded
+    let mut bin = std::fs::read("tests/data/entity_game.bin").unwrap();
+    let mut copy = bin[0x70..0x90].to_vec();
+    for pointer in [0x0, 0x8, 0x1C] {
+        copy[pointer + 3] = 0x90;
+    }
+    bin.extend(copy);
+    let bin_file = TempFile::new("data.bin", &bin);
+
+    let scan_options = ScanOptions {
+        granularity: Granularity::Segment,
+        vram_start: Some(0x80180000),
+        ..Default::default()
+    };
+    let output = scan_output_with(&match_file, &bin_file, &scan_options);
+    let data = serde_yaml::Deserializer::from_str(&output)
+        .map(|document| SegmentOffset::deserialize(document).unwrap())
+        .filter(|so| so.section.is_some())
+        .map(|so| so.offset)
+        .collect::<Vec<_>>();
+    assert_eq!(data, vec![0x70]);
+}
+
+#[test]
+fn test_scan_rodata() {
+    let match_file = tt_004_match_file("rodata");
+    let bin_file = Path::new("tests/data/tt_004_raw.bin");
+    let scan_options = ScanOptions {
+        granularity: Granularity::Segment,
+        vram_start: Some(0x80170000),
+        ..Default::default()
+    };
+
+    let rodata_offset = |match_file: &Path| {
+        let output = scan_output_with(match_file, bin_file, &scan_options);
+        let rodata = serde_yaml::Deserializer::from_str(&output)
+            .map(|document| SegmentOffset::deserialize(document).unwrap())
+            .find(|so| so.name == "sword")
+            .and_then(|so| so.rodata)
+            .map(|rodata| (rodata.offset, rodata.size));
+        rodata
+    };
+
+    // sword's RODATA is "goodbye, world." followed by a jump table
+    assert_eq!(rodata_offset(&match_file), Some((0x954, 0x34)));
+
+    // without a VRAM start, it is inferred from the calls in sword and
+    // servant_common
+    let output = scan_output(&match_file, bin_file, Granularity::Segment);
+    assert!(output.starts_with("# vram_start: 0x80170000 (confidence: high, "));
+    let sword = serde_yaml::Deserializer::from_str(&output)
+        .map(|document| SegmentOffset::deserialize(document).unwrap())
+        .find(|so| so.name == "sword")
+        .unwrap();
+    let rodata = sword.rodata.unwrap();
+    assert_eq!((rodata.offset, rodata.size), (0x954, 0x34));
+    assert_eq!(rodata.jump_tables.len(), 1);
+
+    // the same RODATA described by every classification
+    let config = std::fs::read_to_string(&match_file).unwrap();
+    let sword = || {
+        serde_yaml::Deserializer::from_str(&config)
+            .map(|document| SegmentSignature::deserialize(document).unwrap())
+            .find(|segment| segment.name == "sword")
+            .unwrap()
+    };
+
+    let bin = std::fs::read(bin_file).unwrap();
+    let string = (0x954..0x964)
+        .step_by(4)
+        .map(|i| mips::read_word(&bin[i..(i + 4)], MIPSFamily::R3000GTE))
+        .collect::<Vec<u32>>();
+    let hasher = RabinKarpMIPSHasher::new(MIPSFamily::R3000GTE);
+    let (_, string_hash) = hasher.rolling_hashes(&string, string.len()).next().unwrap();
+
+    let classified = [
+        (RODataSignatureType::OnlyJumpTables, 0x24, None, 0x964),
+        (
+            RODataSignatureType::StartsAndEndsWithJumpTable,
+            0x24,
+            None,
+            0x964,
+        ),
+        (RODataSignatureType::StartsWithJumpTable, 0x24, None, 0x964),
+        (RODataSignatureType::EndsWithJumpTable, 0x34, None, 0x954),
+        (
+            RODataSignatureType::Unknown,
+            0x10,
+            Some(Fingerprint::new_v0(0x10, string_hash)),
+            0x954,
+        ),
+    ];
+    for (rodata_type, size, fingerprint, offset) in classified {
+        let mut segment = sword();
+        segment.rodata = Some(RODataSignature {
+            rodata_type,
+            size,
+            fingerprint,
+            anchor: 0,
+            digest: None,
+            relocations: Vec::new(),
+        });
+        let mut yaml = Vec::new();
+        writeln!(yaml, "---").unwrap();
+        segment.serialize_to_yaml(&mut yaml);
+        let rodata_file = TempFile::new("rodata-types.yaml", yaml);
+
+        assert_eq!(rodata_offset(&rodata_file), Some((offset, size)));
+    }
+
+    // unknown RODATA holding the jump table is found by the string, with
+    // the entries masked, even when they were linked elsewhere
+    let relocations = (0x10..0x34)
+        .step_by(4)
+        .map(|offset| RelocationMask {
+            offset,
+            mask: 0xFFFFFFFF,
+        })
+        .collect::<Vec<_>>();
+    let digest = digest_for_relocated_range(&bin[0x954..0x988], &relocations, MIPSFamily::R3000GTE);
+    let mut segment = sword();
+    segment.rodata = Some(RODataSignature {
+        rodata_type: RODataSignatureType::Unknown,
+        size: 0x34,
+        fingerprint: Some(Fingerprint::new_v0(0x10, string_hash)),
+        anchor: 0,
+        digest: Some(digest),
+        relocations,
+    });
+    let mut yaml = Vec::new();
+    writeln!(yaml, "---").unwrap();
+    segment.serialize_to_yaml(&mut yaml);
+    let rodata_file = TempFile::new("rodata-unknown.yaml", yaml);
+
+    let mut moved = bin.clone();
+    for entry in (0x964..0x988).step_by(4) {
+        moved[entry + 1] ^= 0x10;
+    }
+    let moved_file = TempFile::new("rodata-moved.bin", &moved);
+    let output = scan_output(&rodata_file, &moved_file, Granularity::Segment);
+    let rodata = serde_yaml::Deserializer::from_str(&output)
+        .map(|document| SegmentOffset::deserialize(document).unwrap())
+        .find(|so| so.name == "sword")
+        .and_then(|so| so.rodata)
+        .map(|rodata| (rodata.offset, rodata.size));
+    assert_eq!(rodata, Some((0x954, 0x34)));
+}
+
+#[test]
+fn test_scan_jump_tables() {
+    let match_file = tt_004_match_file("jump-tables");
+    let scan_options = ScanOptions {
+        granularity: Granularity::Segment,
+        vram_start: Some(0x80170000),
+        ..Default::default()
+    };
+
+    let raw = std::fs::read("tests/data/tt_004_raw.bin").expect("Could not read bin file");
+    let bin_file = TempFile::new("jump-tables.bin", &raw);
+
+    let sword_rodata = |bin_file: &Path| {
+        let output = scan_output_with(&match_file, bin_file, &scan_options);
+        let rodata = serde_yaml::Deserializer::from_str(&output)
+            .map(|document| SegmentOffset::deserialize(document).unwrap())
+            .find(|so| so.name == "sword")
+            .and_then(|so| so.rodata);
+        rodata
+    };
+
+    // hello_world dispatches through a table of 9 entries after the string
+    assert_eq!(
+        sword_rodata(&bin_file),
+        Some(RODataOffset {
+            offset: 0x954,
+            size: 0x34,
+            jump_tables: vec![JumpTableOffset {
+                function: "hello_world".to_string(),
+                offset: 0x964,
+                size: 0x24,
+            }],
+        })
+    );
+
+    // the `lui` of the dispatch no longer points at the table, so the bin
+    // is searched for its entries instead
+    let mut edited = raw.clone();
+    edited[0x9AC] = 0x18;
+    bin_file.write(&edited);
+    assert_eq!(
+        sword_rodata(&bin_file),
+        Some(RODataOffset {
+            offset: 0x954,
+            size: 0x34,
+            jump_tables: Vec::new(),
+        })
+    );
+}